@@ -1,11 +1,10 @@
 /**
- * Withdraw NOC from legacy vault PDA and burn it
+ * Move NOC from the legacy vault PDA into the ledgered per-mint vault
  */
 import * as anchor from "@coral-xyz/anchor";
 import { Program, AnchorProvider, Wallet } from "@coral-xyz/anchor";
-import BN from "bn.js";
 import { PublicKey, Keypair, Connection } from "@solana/web3.js";
-import { getAssociatedTokenAddress, TOKEN_PROGRAM_ID, burn } from "@solana/spl-token";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
 import * as fs from "fs";
 import { fileURLToPath } from "url";
 import { dirname, join } from "path";
@@ -47,75 +46,51 @@ async function main() {
   );
   console.log("Legacy Vault Authority:", legacyVaultAuthority.toBase58());
   
-  // Global state PDA
-  const [globalState] = PublicKey.findProgramAddressSync(
-    [Buffer.from("global-state")],
+  // Treasury role PDA of the signer
+  const [adminRole] = PublicKey.findProgramAddressSync(
+    [Buffer.from("role"), adminKeypair.publicKey.toBuffer()],
     PROGRAM_ID
   );
-  console.log("Global State:", globalState.toBase58());
   
-  // Get admin token account
-  const adminNocAta = await getAssociatedTokenAddress(NOC_MINT, adminKeypair.publicKey);
-  
-  console.log("Vault Token Account:", VAULT_TOKEN_ACCOUNT.toBase58());
-  console.log("Admin NOC ATA:", adminNocAta.toBase58());
-  
-  // Check vault balance
-  const vaultBalancePre = await connection.getTokenAccountBalance(VAULT_TOKEN_ACCOUNT);
-  console.log("\nVault NOC balance:", Number(vaultBalancePre.value.amount) / 1e6);
+  // Per-mint vault and its ledger
+  const [vaultTokenAccount] = PublicKey.findProgramAddressSync(
+    [Buffer.from("vault-token"), NOC_MINT.toBuffer()],
+    PROGRAM_ID
+  );
+  const [ledger] = PublicKey.findProgramAddressSync(
+    [Buffer.from("vault-ledger"), NOC_MINT.toBuffer()],
+    PROGRAM_ID
+  );
   
-  const adminBalancePre = await connection.getTokenAccountBalance(adminNocAta);
-  console.log("Admin NOC balance:", Number(adminBalancePre.value.amount) / 1e6);
+  console.log("Legacy Vault Token Account:", VAULT_TOKEN_ACCOUNT.toBase58());
+  console.log("Vault Token Account:", vaultTokenAccount.toBase58());
   
-  // Amount to withdraw (50,000 NOC)
-  const withdrawAmount = new BN(50_000 * 1e6);
+  const legacyBalancePre = await connection.getTokenAccountBalance(VAULT_TOKEN_ACCOUNT);
+  console.log("\nLegacy vault NOC balance:", Number(legacyBalancePre.value.amount) / 1e6);
   
-  console.log("\nWithdrawing 50,000 NOC from legacy vault...");
+  console.log("\nMigrating legacy vault...");
   
   try {
-    const withdrawTx = await program.methods
-      .adminWithdrawLegacyVault(withdrawAmount)
+    const migrateTx = await program.methods
+      .migrateLegacyVault()
       .accounts({
         admin: adminKeypair.publicKey,
-        globalState: globalState,
+        adminRole: adminRole,
         mint: NOC_MINT,
         legacyVaultAuthority: legacyVaultAuthority,
-        vaultTokenAccount: VAULT_TOKEN_ACCOUNT,
-        destination: adminNocAta,
+        legacyVaultTokenAccount: VAULT_TOKEN_ACCOUNT,
+        vaultTokenAccount: vaultTokenAccount,
+        ledger: ledger,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([adminKeypair])
       .rpc();
     
-    console.log("Withdraw tx:", withdrawTx);
+    console.log("Migrate tx:", migrateTx);
     
-    // Check new balances
-    const vaultBalancePost = await connection.getTokenAccountBalance(VAULT_TOKEN_ACCOUNT);
+    const vaultBalancePost = await connection.getTokenAccountBalance(vaultTokenAccount);
     console.log("\nVault NOC after:", Number(vaultBalancePost.value.amount) / 1e6);
     
-    const adminBalancePost = await connection.getTokenAccountBalance(adminNocAta);
-    console.log("Admin NOC after:", Number(adminBalancePost.value.amount) / 1e6);
-    
-    // Burn the withdrawn tokens
-    console.log("\nBurning 50,000 NOC...");
-    const burnSig = await burn(
-      connection,
-      adminKeypair,
-      adminNocAta,
-      NOC_MINT,
-      adminKeypair,
-      50_000 * 1e6
-    );
-    console.log("Burn tx:", burnSig);
-    
-    // Final balance
-    const adminFinal = await connection.getTokenAccountBalance(adminNocAta);
-    console.log("\nAdmin NOC final:", Number(adminFinal.value.amount) / 1e6);
-    
-    // Check supply
-    const supplyInfo = await connection.getTokenSupply(NOC_MINT);
-    console.log("Total NOC supply:", Number(supplyInfo.value.amount) / 1e6);
-    
   } catch (e) {
     console.error("Error:", e);
   }
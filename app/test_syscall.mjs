@@ -1,28 +1,34 @@
 import * as anchor from "@coral-xyz/anchor";
-import { Program, AnchorProvider } from "@coral-xyz/anchor";
+import { createHash } from "crypto";
 import fs from "fs";
-import path from "path";
-import { fileURLToPath } from "url";
 
-const __filename = fileURLToPath(import.meta.url);
-const __dirname = path.dirname(__filename);
-
-// Load IDL
-const idlPath = path.join(__dirname, "../target/idl/noctura_shield.json");
-const idl = JSON.parse(fs.readFileSync(idlPath, "utf8"));
-
-// Set up provider
+// test_scalar_mul is dispatched by the devnet fallback and is not in the IDL,
+// so the instruction is built by hand: sighash + borsh Vec<u8>
 const connection = new anchor.web3.Connection("https://api.testnet.solana.com", "confirmed");
-const wallet = new anchor.Wallet(
-  anchor.web3.Keypair.fromSecretKey(
-    Uint8Array.from(JSON.parse(fs.readFileSync("/Users/banel/config/solana/id.json", "utf8")))
-  )
+const payer = anchor.web3.Keypair.fromSecretKey(
+  Uint8Array.from(JSON.parse(fs.readFileSync("/Users/banel/config/solana/id.json", "utf8")))
 );
-const provider = new AnchorProvider(connection, wallet, { commitment: "confirmed" });
-anchor.setProvider(provider);
 
 const PROGRAM_ID = new anchor.web3.PublicKey("3KN2qrmEtPyk9WGu9jJSzLerxU8AUXAy8Dp6bqw5APDz");
-const program = new Program(idl, PROGRAM_ID, provider);
+
+function getInstructionDiscriminator(name) {
+  return createHash("sha256").update(`global:${name}`).digest().subarray(0, 8);
+}
+
+function printProgramData(logs, prefix) {
+  for (const log of logs) {
+    if (log.startsWith("Program data:")) {
+      const parts = log.replace("Program data: ", "").split(" ");
+      const label = Buffer.from(parts[0], "base64").toString();
+      const data = parts.length > 1 ? Buffer.from(parts[1], "base64") : null;
+      if (data) {
+        console.log(`${prefix}${label}: ${data.toString("hex")}`);
+      } else {
+        console.log(`${prefix}${label}`);
+      }
+    }
+  }
+}
 
 // Known-good test vector from Solana SDK (cdetrio5): point * 1 = point
 // This MUST work if the syscall is functioning correctly
@@ -46,50 +52,35 @@ async function testScalarMul(name, inputHex) {
   console.log(`Input length: ${inputHex.length / 2} bytes`);
   
   const inputBuffer = Buffer.from(inputHex, "hex");
+  const len = Buffer.alloc(4);
+  len.writeUInt32LE(inputBuffer.length);
   
-  try {
-    const tx = await program.methods
-      .testScalarMul(inputBuffer)
-      .accounts({})
-      .simulate();
-    
+  const ix = new anchor.web3.TransactionInstruction({
+    programId: PROGRAM_ID,
+    keys: [],
+    data: Buffer.concat([getInstructionDiscriminator("test_scalar_mul"), len, inputBuffer]),
+  });
+  const tx = new anchor.web3.Transaction().add(ix);
+  tx.feePayer = payer.publicKey;
+  tx.recentBlockhash = (await connection.getLatestBlockhash()).blockhash;
+  
+  const { value } = await connection.simulateTransaction(tx, [payer]);
+  const logs = value.logs || [];
+  
+  if (!value.err) {
     console.log("SUCCESS!");
-    console.log("Logs:", tx.raw);
-    
-    // Parse the output from logs
-    for (const log of tx.raw || []) {
-      if (log.startsWith("Program data:")) {
-        const parts = log.replace("Program data: ", "").split(" ");
-        const label = Buffer.from(parts[0], "base64").toString();
-        const data = parts.length > 1 ? Buffer.from(parts[1], "base64") : null;
-        if (data) {
-          console.log(`  ${label}: ${data.toString("hex")}`);
-        } else {
-          console.log(`  ${label}`);
-        }
-      }
-    }
+    console.log("Logs:", logs);
+    printProgramData(logs, "  ");
     return true;
-  } catch (err) {
-    console.log("FAILED!");
-    if (err.simulationResponse?.logs) {
-      console.log("Logs:");
-      for (const log of err.simulationResponse.logs) {
-        console.log(" ", log);
-        if (log.startsWith("Program data:")) {
-          const parts = log.replace("Program data: ", "").split(" ");
-          const label = Buffer.from(parts[0], "base64").toString();
-          const data = parts.length > 1 ? Buffer.from(parts[1], "base64") : null;
-          if (data) {
-            console.log(`    -> ${label}: ${data.toString("hex")}`);
-          } else {
-            console.log(`    -> ${label}`);
-          }
-        }
-      }
-    }
-    return false;
   }
+  
+  console.log("FAILED!", value.err);
+  console.log("Logs:");
+  for (const log of logs) {
+    console.log(" ", log);
+  }
+  printProgramData(logs, "    -> ");
+  return false;
 }
 
 async function main() {
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::ShieldError, state::SupportedAsset};
+
+impl SupportedAsset {
+    /// Count a deposit of `amount` against the asset's limits.
+    pub fn record_deposit(&mut self, amount: u64) -> Result<()> {
+        self.require_enabled()?;
+        require!(
+            self.max_deposit == 0 || amount <= self.max_deposit,
+            ShieldError::DepositCapExceeded
+        );
+        let total = self
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(ShieldError::CapacityExceeded)?;
+        require!(
+            self.deposit_cap == 0 || total <= self.deposit_cap,
+            ShieldError::DepositCapExceeded
+        );
+        self.total_deposited = total;
+        Ok(())
+    }
+
+    /// Free deposit room as notes are withdrawn. Swapped value can leave through an asset
+    /// it did not enter by, so this saturates rather than fails.
+    pub fn record_withdrawal(&mut self, amount: u64) {
+        self.total_deposited = self.total_deposited.saturating_sub(amount);
+    }
+
+    pub fn require_enabled(&self) -> Result<()> {
+        require!(self.enabled, ShieldError::AssetDisabled);
+        Ok(())
+    }
+}
+
+/// The (input, output) assets of a pool swap; both must be enabled.
+pub fn swap_assets<'a>(
+    sol_asset: &'a SupportedAsset,
+    noc_asset: &'a SupportedAsset,
+    input_is_sol: bool,
+) -> Result<(&'a SupportedAsset, &'a SupportedAsset)> {
+    sol_asset.require_enabled()?;
+    noc_asset.require_enabled()?;
+    Ok(if input_is_sol {
+        (sol_asset, noc_asset)
+    } else {
+        (noc_asset, sol_asset)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deposits_respect_single_and_total_limits() {
+        let mut asset = SupportedAsset {
+            mint: Pubkey::new_unique(),
+            mint_field: [0u8; 32],
+            decimals: 6,
+            enabled: true,
+            max_deposit: 500,
+            deposit_cap: 1_000,
+            total_deposited: 0,
+            bump: 255,
+        };
+        assert!(asset.record_deposit(501).is_err());
+        asset.record_deposit(500).unwrap();
+        asset.record_deposit(500).unwrap();
+        assert!(asset.record_deposit(1).is_err());
+
+        asset.record_withdrawal(300);
+        asset.record_deposit(300).unwrap();
+
+        asset.enabled = false;
+        asset.record_withdrawal(1_000);
+        assert!(asset.record_deposit(1).is_err());
+    }
+}
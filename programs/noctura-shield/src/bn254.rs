@@ -0,0 +1,424 @@
+//! BN254 (alt_bn128) base-field and curve arithmetic for point validation.
+//!
+//! The alt_bn128 syscalls only cover G1 addition, G1 multiplication and the pairing.
+//! The pairing does reject G2 inputs outside the subgroup, but only as an opaque failure
+//! on every proof, so the checks that let a bad verifying key be refused once, with a
+//! specific error, live here. Field elements are four little-endian u64 limbs in
+//! Montgomery form; byte encodings are EIP-196 big-endian.
+use anchor_lang::prelude::*;
+
+use crate::errors::ShieldError;
+
+// p = 21888242871839275222246405745257275088696311157297823662689037894645226208583
+const P: [u64; 4] = [0x3c208c16d87cfd47, 0x97816a916871ca8d, 0xb85045b68181585d, 0x30644e72e131a029];
+// -p^-1 mod 2^64
+const INV: u64 = 0x87d20782e4866389;
+// 2^512 mod p, converts a canonical value into Montgomery form
+const R2: Fp = Fp([0xf32cfc5b538afa89, 0xb5e71911d44501fb, 0x47ab1eff0a417ff6, 0x06d89f71cab8351f]);
+// Curve parameter x of BN254, the seed of p and r
+const BN_X: u64 = 0x44e992b44a6909f1;
+
+/// G1: y^2 = x^3 + 3
+const G1_B: Fp = Fp([0x7a17caa950ad28d7, 0x1f6ac17ae15521b9, 0x334bea4e696bd284, 0x2a1f6744ce179d8e]);
+/// G2 twist: y^2 = x^3 + 3 / (9 + u)
+const G2_B: Fp2 = Fp2 {
+    c0: Fp([0x3bf938e377b802a8, 0x020b1b273633535d, 0x26b7edf049755260, 0x2514c6324384a86d]),
+    c1: Fp([0x38e7ecccd1dcff67, 0x65f0b37d93ce0d3e, 0xd749d0dd22ac00aa, 0x0141b9ce4a688d4d]),
+};
+/// (9 + u)^((p - 1) / 3) and (9 + u)^((p - 1) / 2), the coefficients of the untwist-Frobenius-twist map psi.
+const PSI_X: Fp2 = Fp2 {
+    c0: Fp([0xb5773b104563ab30, 0x347f91c8a9aa6454, 0x7a007127242e0991, 0x1956bcd8118214ec]),
+    c1: Fp([0x6e849f1ea0aa4757, 0xaa1c7b6d89f89141, 0xb6e713cdfae0ca3a, 0x26694fbb4e82ebc3]),
+};
+const PSI_Y: Fp2 = Fp2 {
+    c0: Fp([0xe4bbdd0c2936b629, 0xbb30f162e133bacb, 0x31a9d1b6f9645366, 0x253570bea500f8dd]),
+    c1: Fp([0xa1d77ce45ffe77c7, 0x07affd117826d1db, 0x6d16bd27bb7edc6b, 0x2c87200285defecc]),
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Fp([u64; 4]);
+
+impl Fp {
+    const ZERO: Fp = Fp([0; 4]);
+    const ONE: Fp = Fp([0xd35d438dc58f0d9d, 0x0a78eb28f5c70b3d, 0x666ea36f7879462c, 0x0e0a77c19a07df2f]);
+
+    /// Decode a big-endian coordinate, rejecting values that are not below p.
+    fn from_be_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut limbs = [0u64; 4];
+        for (i, chunk) in bytes.chunks_exact(8).enumerate() {
+            limbs[3 - i] = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+        require!(less_than(&limbs, &P), ShieldError::NonCanonicalCoordinate);
+        Ok(Fp(limbs).mul(&R2))
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == [0; 4]
+    }
+
+    fn add(&self, rhs: &Self) -> Self {
+        let mut out = [0u64; 4];
+        let mut carry = 0u64;
+        for (i, limb) in out.iter_mut().enumerate() {
+            let (sum, c1) = self.0[i].overflowing_add(rhs.0[i]);
+            let (sum, c2) = sum.overflowing_add(carry);
+            *limb = sum;
+            carry = (c1 | c2) as u64;
+        }
+        // p < 2^254, so the sum never carries out of the top limb
+        if !less_than(&out, &P) {
+            sub_limbs(&mut out, &P);
+        }
+        Fp(out)
+    }
+
+    fn sub(&self, rhs: &Self) -> Self {
+        let mut out = self.0;
+        if sub_limbs(&mut out, &rhs.0) {
+            add_limbs(&mut out, &P);
+        }
+        Fp(out)
+    }
+
+    /// Montgomery multiplication (CIOS).
+    fn mul(&self, rhs: &Self) -> Self {
+        let mut t = [0u64; 6];
+        for &b in rhs.0.iter() {
+            let mut carry = 0u64;
+            for (t_j, &a) in t.iter_mut().zip(self.0.iter()) {
+                let v = *t_j as u128 + a as u128 * b as u128 + carry as u128;
+                *t_j = v as u64;
+                carry = (v >> 64) as u64;
+            }
+            let v = t[4] as u128 + carry as u128;
+            t[4] = v as u64;
+            t[5] = (v >> 64) as u64;
+
+            let m = t[0].wrapping_mul(INV);
+            let v = t[0] as u128 + m as u128 * P[0] as u128;
+            let mut carry = (v >> 64) as u64;
+            for j in 1..4 {
+                let v = t[j] as u128 + m as u128 * P[j] as u128 + carry as u128;
+                t[j - 1] = v as u64;
+                carry = (v >> 64) as u64;
+            }
+            let v = t[4] as u128 + carry as u128;
+            t[3] = v as u64;
+            t[4] = t[5] + (v >> 64) as u64;
+        }
+        let mut out = [t[0], t[1], t[2], t[3]];
+        if t[4] != 0 || !less_than(&out, &P) {
+            sub_limbs(&mut out, &P);
+        }
+        Fp(out)
+    }
+
+    fn square(&self) -> Self {
+        self.mul(self)
+    }
+}
+
+fn less_than(a: &[u64; 4], b: &[u64; 4]) -> bool {
+    for i in (0..4).rev() {
+        if a[i] != b[i] {
+            return a[i] < b[i];
+        }
+    }
+    false
+}
+
+/// `a -= b`, returning the final borrow.
+fn sub_limbs(a: &mut [u64; 4], b: &[u64; 4]) -> bool {
+    let mut borrow = false;
+    for (a_i, &b_i) in a.iter_mut().zip(b.iter()) {
+        let (diff, b1) = a_i.overflowing_sub(b_i);
+        let (diff, b2) = diff.overflowing_sub(borrow as u64);
+        *a_i = diff;
+        borrow = b1 | b2;
+    }
+    borrow
+}
+
+fn add_limbs(a: &mut [u64; 4], b: &[u64; 4]) {
+    let mut carry = false;
+    for (a_i, &b_i) in a.iter_mut().zip(b.iter()) {
+        let (sum, c1) = a_i.overflowing_add(b_i);
+        let (sum, c2) = sum.overflowing_add(carry as u64);
+        *a_i = sum;
+        carry = c1 | c2;
+    }
+}
+
+/// Fp2 = Fp[u] / (u^2 + 1)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Fp2 {
+    c0: Fp,
+    c1: Fp,
+}
+
+impl Fp2 {
+    const ZERO: Fp2 = Fp2 { c0: Fp::ZERO, c1: Fp::ZERO };
+
+    /// EIP-196 orders the imaginary part first: `c1 || c0`.
+    fn from_be_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Fp2 {
+            c1: Fp::from_be_bytes(&bytes[..32])?,
+            c0: Fp::from_be_bytes(&bytes[32..64])?,
+        })
+    }
+
+    fn is_zero(&self) -> bool {
+        self.c0.is_zero() && self.c1.is_zero()
+    }
+
+    fn add(&self, rhs: &Self) -> Self {
+        Fp2 { c0: self.c0.add(&rhs.c0), c1: self.c1.add(&rhs.c1) }
+    }
+
+    fn sub(&self, rhs: &Self) -> Self {
+        Fp2 { c0: self.c0.sub(&rhs.c0), c1: self.c1.sub(&rhs.c1) }
+    }
+
+    fn double(&self) -> Self {
+        self.add(self)
+    }
+
+    fn mul(&self, rhs: &Self) -> Self {
+        let v0 = self.c0.mul(&rhs.c0);
+        let v1 = self.c1.mul(&rhs.c1);
+        let cross = self.c0.add(&self.c1).mul(&rhs.c0.add(&rhs.c1));
+        Fp2 { c0: v0.sub(&v1), c1: cross.sub(&v0).sub(&v1) }
+    }
+
+    fn square(&self) -> Self {
+        self.mul(self)
+    }
+
+    /// The p-power Frobenius map.
+    fn conjugate(&self) -> Self {
+        Fp2 { c0: self.c0, c1: Fp::ZERO.sub(&self.c1) }
+    }
+}
+
+/// Decode an EIP-196 G1 point and check it lies on the curve; G1 has cofactor 1, so
+/// that is also subgroup membership. Returns `false` for the point at infinity (all zeros).
+pub fn check_g1(bytes: &[u8; 64]) -> Result<bool> {
+    if bytes.iter().all(|b| *b == 0) {
+        return Ok(false);
+    }
+    let x = Fp::from_be_bytes(&bytes[..32])?;
+    let y = Fp::from_be_bytes(&bytes[32..])?;
+    require!(y.square() == x.square().mul(&x).add(&G1_B), ShieldError::PointNotOnCurve);
+    Ok(true)
+}
+
+/// Decode an EIP-196 G2 point and check it lies on the twist. Returns `false` for the
+/// point at infinity. Subgroup membership is the separate, far more expensive
+/// [`require_g2_subgroup`].
+pub fn check_g2(bytes: &[u8; 128]) -> Result<bool> {
+    Ok(decode_g2(bytes)?.is_some())
+}
+
+/// Reject a G2 point outside the order-r subgroup.
+///
+/// Uses the endomorphism test `[x+1]Q + psi([x]Q) + psi^2([x]Q) == psi^3([2x]Q)`
+/// (El Housni, Guillevic, Piellard 2022), which costs one 63-bit scalar multiplication.
+pub fn require_g2_subgroup(bytes: &[u8; 128]) -> Result<()> {
+    let Some((x, y)) = decode_g2(bytes)? else {
+        return Ok(());
+    };
+    let q = G2Jacobian { x, y, z: Fp2 { c0: Fp::ONE, c1: Fp::ZERO } };
+    let xq = q.mul_u64(BN_X);
+    let lhs = xq.add(&q).add(&xq.psi()).add(&xq.psi().psi());
+    let rhs = xq.double().psi().psi().psi();
+    require!(lhs.eq(&rhs), ShieldError::PointNotInSubgroup);
+    Ok(())
+}
+
+fn decode_g2(bytes: &[u8; 128]) -> Result<Option<(Fp2, Fp2)>> {
+    if bytes.iter().all(|b| *b == 0) {
+        return Ok(None);
+    }
+    let x = Fp2::from_be_bytes(&bytes[..64])?;
+    let y = Fp2::from_be_bytes(&bytes[64..])?;
+    require!(y.square() == x.square().mul(&x).add(&G2_B), ShieldError::PointNotOnCurve);
+    Ok(Some((x, y)))
+}
+
+/// G2 point in Jacobian coordinates: (X / Z^2, Y / Z^3); Z = 0 is the identity.
+#[derive(Clone, Copy)]
+struct G2Jacobian {
+    x: Fp2,
+    y: Fp2,
+    z: Fp2,
+}
+
+impl G2Jacobian {
+    const IDENTITY: G2Jacobian = G2Jacobian { x: Fp2::ZERO, y: Fp2::ZERO, z: Fp2::ZERO };
+
+    fn is_identity(&self) -> bool {
+        self.z.is_zero()
+    }
+
+    /// dbl-2009-l
+    fn double(&self) -> Self {
+        if self.is_identity() {
+            return *self;
+        }
+        let a = self.x.square();
+        let b = self.y.square();
+        let c = b.square();
+        let d = self.x.add(&b).square().sub(&a).sub(&c).double();
+        let e = a.double().add(&a);
+        let f = e.square();
+        let x3 = f.sub(&d.double());
+        let y3 = e.mul(&d.sub(&x3)).sub(&c.double().double().double());
+        let z3 = self.y.mul(&self.z).double();
+        G2Jacobian { x: x3, y: y3, z: z3 }
+    }
+
+    /// add-2007-bl, falling back to doubling when both inputs are the same point.
+    fn add(&self, rhs: &Self) -> Self {
+        if self.is_identity() {
+            return *rhs;
+        }
+        if rhs.is_identity() {
+            return *self;
+        }
+        let z1z1 = self.z.square();
+        let z2z2 = rhs.z.square();
+        let u1 = self.x.mul(&z2z2);
+        let u2 = rhs.x.mul(&z1z1);
+        let s1 = self.y.mul(&rhs.z).mul(&z2z2);
+        let s2 = rhs.y.mul(&self.z).mul(&z1z1);
+        let h = u2.sub(&u1);
+        let r = s2.sub(&s1).double();
+        if h.is_zero() {
+            return if r.is_zero() { self.double() } else { Self::IDENTITY };
+        }
+        let i = h.double().square();
+        let j = h.mul(&i);
+        let v = u1.mul(&i);
+        let x3 = r.square().sub(&j).sub(&v.double());
+        let y3 = r.mul(&v.sub(&x3)).sub(&s1.mul(&j).double());
+        let z3 = self.z.add(&rhs.z).square().sub(&z1z1).sub(&z2z2).mul(&h);
+        G2Jacobian { x: x3, y: y3, z: z3 }
+    }
+
+    fn mul_u64(&self, scalar: u64) -> Self {
+        let mut acc = Self::IDENTITY;
+        for bit in (0..64 - scalar.leading_zeros()).rev() {
+            acc = acc.double();
+            if (scalar >> bit) & 1 == 1 {
+                acc = acc.add(self);
+            }
+        }
+        acc
+    }
+
+    /// psi(x, y) = (conj(x) * PSI_X, conj(y) * PSI_Y), applied to the Jacobian coordinates.
+    fn psi(&self) -> Self {
+        G2Jacobian {
+            x: self.x.conjugate().mul(&PSI_X),
+            y: self.y.conjugate().mul(&PSI_Y),
+            z: self.z.conjugate(),
+        }
+    }
+
+    fn eq(&self, rhs: &Self) -> bool {
+        if self.is_identity() || rhs.is_identity() {
+            return self.is_identity() && rhs.is_identity();
+        }
+        let z1z1 = self.z.square();
+        let z2z2 = rhs.z.square();
+        self.x.mul(&z2z2) == rhs.x.mul(&z1z1)
+            && self.y.mul(&rhs.z).mul(&z2z2) == rhs.y.mul(&self.z).mul(&z1z1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex32(text: &str) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&text[2 * i..2 * i + 2], 16).unwrap();
+        }
+        out
+    }
+
+    fn g2(coords: [&str; 4]) -> [u8; 128] {
+        let mut out = [0u8; 128];
+        for (i, coord) in coords.iter().enumerate() {
+            out[32 * i..32 * (i + 1)].copy_from_slice(&hex32(coord));
+        }
+        out
+    }
+
+    // x.c1, x.c0, y.c1, y.c0 of the standard G2 generator
+    const G2_GENERATOR: [&str; 4] = [
+        "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2",
+        "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed",
+        "090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b",
+        "12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+    ];
+
+    // On the twist, but not in the order-r subgroup
+    const G2_OFF_SUBGROUP: [&str; 4] = [
+        "0a7a0869c74803e31ba1621582283d15a9ec0806705fca161622bd795fec898f",
+        "2f3eec14acab1a6bc69d4bd8b3fa7aa7e1fab9d78c7e134f5dfbd3d12c4a3698",
+        "2b252025afe102b3d1c9cbb5a369df67f719ea6cda1ffaf9fabea2f3773d2ec5",
+        "2cbb4dbfdeddf3356bdc0ff69e929b1733b677be780ab7b98906c09492b8a97a",
+    ];
+
+    #[test]
+    fn g1_generator_is_on_curve_and_tweaked_point_is_not() {
+        let mut point = [0u8; 64];
+        point[31] = 1;
+        point[63] = 2;
+        assert!(check_g1(&point).unwrap());
+
+        point[63] = 3;
+        assert_eq!(check_g1(&point).unwrap_err(), error!(ShieldError::PointNotOnCurve));
+
+        assert!(!check_g1(&[0u8; 64]).unwrap());
+    }
+
+    #[test]
+    fn coordinate_at_base_field_modulus_is_rejected() {
+        let mut point = [0u8; 64];
+        point[..32].copy_from_slice(&hex32("30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47"));
+        point[63] = 2;
+        assert_eq!(check_g1(&point).unwrap_err(), error!(ShieldError::NonCanonicalCoordinate));
+    }
+
+    #[test]
+    fn g2_subgroup_check_separates_twist_points() {
+        let generator = g2(G2_GENERATOR);
+        assert!(check_g2(&generator).unwrap());
+        assert!(require_g2_subgroup(&generator).is_ok());
+
+        let off = g2(G2_OFF_SUBGROUP);
+        assert!(check_g2(&off).unwrap());
+        assert_eq!(require_g2_subgroup(&off).unwrap_err(), error!(ShieldError::PointNotInSubgroup));
+
+        let mut tweaked = generator;
+        tweaked[127] ^= 1;
+        assert_eq!(check_g2(&tweaked).unwrap_err(), error!(ShieldError::PointNotOnCurve));
+    }
+
+    #[test]
+    fn pairing_rejects_g2_points_outside_the_subgroup() {
+        use anchor_lang::solana_program::alt_bn128::prelude::alt_bn128_pairing;
+
+        let mut input = [0u8; 192];
+        input[31] = 1;
+        input[63] = 2;
+        input[64..].copy_from_slice(&g2(G2_GENERATOR));
+        assert!(alt_bn128_pairing(&input).is_ok());
+
+        input[64..].copy_from_slice(&g2(G2_OFF_SUBGROUP));
+        assert!(alt_bn128_pairing(&input).is_err());
+    }
+}
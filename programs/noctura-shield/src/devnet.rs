@@ -0,0 +1,119 @@
+//! Devnet-only maintenance instructions, compiled in with the `devnet` feature.
+//!
+//! Anchor 0.29 ignores `#[cfg]` on instructions inside `#[program]`, so these live
+//! outside it and are reached through the program's `fallback`. They keep their
+//! usual `global:<name>` discriminators, so existing scripts can still call them.
+use std::collections::BTreeSet;
+
+use anchor_lang::{prelude::*, solana_program::hash::hash};
+
+use crate::{
+    errors::ShieldError,
+    state::{GlobalState, NullifierSetAccount, Role, RoleAccount},
+    GLOBAL_STATE_SEED, NULLIFIER_SEED, ROLE_SEED,
+};
+
+pub fn dispatch<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    data: &[u8],
+) -> Result<()> {
+    require!(data.len() >= 8, anchor_lang::error::ErrorCode::InstructionMissing);
+    let (sighash, ix_data) = data.split_at(8);
+
+    if sighash == discriminator("reset_nullifiers") {
+        let mut bumps = ResetNullifiersBumps::default();
+        let mut remaining = accounts;
+        let mut ix = ResetNullifiers::try_accounts(program_id, &mut remaining, ix_data, &mut bumps, &mut BTreeSet::new())?;
+        reset_nullifiers(Context::new(program_id, &mut ix, remaining, bumps))?;
+        ix.exit(program_id)
+    } else if sighash == discriminator("emergency_reset_nullifiers") {
+        let mut bumps = EmergencyResetNullifiersBumps::default();
+        let mut remaining = accounts;
+        let mut ix =
+            EmergencyResetNullifiers::try_accounts(program_id, &mut remaining, ix_data, &mut bumps, &mut BTreeSet::new())?;
+        emergency_reset_nullifiers(Context::new(program_id, &mut ix, remaining, bumps))?;
+        ix.exit(program_id)
+    } else if sighash == discriminator("test_scalar_mul") {
+        let input = Vec::<u8>::deserialize(&mut &ix_data[..])
+            .map_err(|_| anchor_lang::error::ErrorCode::InstructionDidNotDeserialize)?;
+        test_scalar_mul(input)
+    } else {
+        Err(anchor_lang::error::ErrorCode::InstructionFallbackNotFound.into())
+    }
+}
+
+fn discriminator(name: &str) -> [u8; 8] {
+    let mut out = [0u8; 8];
+    out.copy_from_slice(&hash(format!("global:{}", name).as_bytes()).to_bytes()[..8]);
+    out
+}
+
+/// Admin function to reset the nullifier set (for devnet testing only)
+/// WARNING: This allows double-spending of previously spent notes!
+fn reset_nullifiers(ctx: Context<ResetNullifiers>) -> Result<()> {
+    ctx.accounts.nullifier_set.nullifiers = Vec::new();
+    msg!("Nullifier set reset by admin");
+    Ok(())
+}
+
+/// DEVNET ONLY: Guardian reset of the nullifier set without the global state account
+/// This allows resetting the nullifier set when the global state can't be loaded
+fn emergency_reset_nullifiers(ctx: Context<EmergencyResetNullifiers>) -> Result<()> {
+    msg!("EMERGENCY: Resetting nullifier set (devnet only)");
+    ctx.accounts.nullifier_set.nullifiers.clear();
+    msg!("Nullifier set cleared, new count: {}", ctx.accounts.nullifier_set.nullifiers.len());
+    Ok(())
+}
+
+/// Debug instruction to test alt_bn128 syscall directly
+fn test_scalar_mul(input: Vec<u8>) -> Result<()> {
+    use solana_program::alt_bn128::prelude::alt_bn128_multiplication;
+    use solana_program::log::sol_log_data;
+
+    sol_log_data(&[b"test.input.len", &(input.len() as u64).to_le_bytes()]);
+    sol_log_data(&[b"test.input", &input]);
+
+    match alt_bn128_multiplication(&input) {
+        Ok(result) => {
+            sol_log_data(&[b"test.success", &result]);
+            Ok(())
+        }
+        Err(e) => {
+            let err_code: u64 = e.into();
+            sol_log_data(&[b"test.error", &err_code.to_le_bytes()]);
+            Err(error!(ShieldError::InvalidProof))
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct ResetNullifiers<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::Guardian) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [NULLIFIER_SEED], bump)]
+    pub nullifier_set: Account<'info, NullifierSetAccount>,
+}
+
+/// DEVNET ONLY: Emergency reset, gated by the Guardian role like `ResetNullifiers`
+#[derive(Accounts)]
+pub struct EmergencyResetNullifiers<'info> {
+    #[account(mut)]
+    pub guardian: Signer<'info>,
+    #[account(
+        seeds = [ROLE_SEED, guardian.key().as_ref()],
+        bump = guardian_role.bump,
+        constraint = guardian_role.has(Role::Guardian) @ ShieldError::MissingRole
+    )]
+    pub guardian_role: Account<'info, RoleAccount>,
+    #[account(mut, seeds = [NULLIFIER_SEED], bump)]
+    pub nullifier_set: Account<'info, NullifierSetAccount>,
+}
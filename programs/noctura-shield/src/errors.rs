@@ -18,4 +18,62 @@ pub enum ShieldError {
     VerifierMissing,
     #[msg("Verifier parameters are malformed")]
     InvalidVerifierKey,
+    #[msg("Proof public inputs do not match the instruction arguments")]
+    PublicInputMismatch,
+    #[msg("Merkle root is not in the tree's recent root history")]
+    UnknownMerkleRoot,
+    #[msg("Value is not a canonical BN254 scalar field element")]
+    NonCanonicalFieldElement,
+    #[msg("Nullifier record account does not match the nullifier PDA")]
+    InvalidNullifierAccount,
+    #[msg("Signer does not hold the role required for this instruction")]
+    MissingRole,
+    #[msg("Not enough admin co-signers approved this action")]
+    InsufficientApprovals,
+    #[msg("Multisig threshold must be between 1 and the number of signers")]
+    InvalidThreshold,
+    #[msg("No pending governance proposal with this id")]
+    ProposalNotFound,
+    #[msg("Governance proposal is still timelocked")]
+    ProposalNotReady,
+    #[msg("Governance proposal does not match the supplied accounts")]
+    ProposalMismatch,
+    #[msg("Verifier has been finalized and can no longer change")]
+    VerifierFinalized,
+    #[msg("Uploaded verifying key does not match the expected hash")]
+    VerifierHashMismatch,
+    #[msg("Curve point coordinate is not below the BN254 base field modulus")]
+    NonCanonicalCoordinate,
+    #[msg("Point is not on the BN254 curve")]
+    PointNotOnCurve,
+    #[msg("G2 point is not in the prime-order subgroup")]
+    PointNotInSubgroup,
+    #[msg("Proof point is the point at infinity")]
+    ProofPointAtInfinity,
+    #[msg("Proof length or version byte is not a supported encoding")]
+    InvalidProofEncoding,
+    #[msg("Fee rule must be at most 10000 bps with a max fee of 0 or at least the min fee")]
+    InvalidFeeRule,
+    #[msg("Fee would consume the whole amount")]
+    FeeExceedsAmount,
+    #[msg("Relayer account does not match the relayer bound into the proof")]
+    InvalidRelayer,
+    #[msg("Asset is disabled for deposits and swaps")]
+    AssetDisabled,
+    #[msg("Deposit exceeds the asset's deposit limits")]
+    DepositCapExceeded,
+    #[msg("Mint has a Token-2022 extension the vaults do not support")]
+    UnsupportedMintExtension,
+    #[msg("Vault balance would not cover what it owes to notes and the pool")]
+    VaultInsolvent,
+    #[msg("Account is already in the current layout")]
+    AccountAlreadyMigrated,
+    #[msg("Merkle tree account is in the legacy layout; run migrate_merkle_tree")]
+    MerkleTreeNeedsMigration,
+    #[msg("Legacy tree frontier does not reproduce its latest root")]
+    LegacyTreeMismatch,
+    #[msg("Verifying key public input count does not match the circuit's layout")]
+    PublicInputCountMismatch,
+    #[msg("Verifying key G2 points have not all passed check_verifier_subgroup")]
+    VerifierSubgroupUnchecked,
 }
@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+use crate::state::{FeeOperation, GovernanceAction, Role};
+
 #[event]
 pub struct CommitmentInserted {
     pub commitment: [u8; 32],
@@ -51,3 +53,93 @@ pub struct TransparentSwapExecuted {
     pub input_amount: u64,
     pub output_amount: u64,
 }
+
+/// Pool reserves re-derived from the pool vault balances; the skimmed amounts went to
+/// the fee collector.
+#[event]
+pub struct PoolReservesSynced {
+    pub sol_reserve: u64,
+    pub noc_reserve: u64,
+    pub skimmed_sol: u64,
+    pub skimmed_noc: u64,
+}
+
+#[event]
+pub struct RoleGranted {
+    pub holder: Pubkey,
+    pub role: Role,
+}
+
+#[event]
+pub struct RoleRevoked {
+    pub holder: Pubkey,
+    pub role: Role,
+}
+
+#[event]
+pub struct AdminProposed {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+}
+
+#[event]
+pub struct AdminAccepted {
+    pub previous_admin: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct AdminMultisigUpdated {
+    pub threshold: u8,
+    pub signer_count: u8,
+}
+
+#[event]
+pub struct ProposalQueued {
+    pub id: u64,
+    pub eta: i64,
+    pub action: GovernanceAction,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub id: u64,
+}
+
+#[event]
+pub struct ProposalCancelled {
+    pub id: u64,
+}
+
+/// Emitted for every fee-bearing operation, including those charged zero.
+#[event]
+pub struct FeeCharged {
+    pub operation: FeeOperation,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct RelayerPaid {
+    pub relayer: Pubkey,
+    pub mint: Pubkey,
+    pub fee: u64,
+}
+
+#[event]
+pub struct AssetRegistered {
+    pub mint: Pubkey,
+    pub mint_field: [u8; 32],
+}
+
+/// A vault's surplus over its liabilities paid to the fee collector; `fees` is the
+/// part of it the ledger had recorded as accrued fees.
+#[event]
+pub struct VaultSurplusSwept {
+    pub mint: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub fees: u64,
+    pub liabilities: u128,
+}
@@ -0,0 +1,198 @@
+use anchor_lang::{prelude::*, system_program};
+
+use crate::{
+    errors::ShieldError,
+    events::FeeCharged,
+    state::{FeeOperation, FeeRule, FeeSchedule, MintFeeOverride, MAX_FEE_OVERRIDES},
+    token_vault::CheckedTransfer,
+};
+
+/// Key under which native SOL fees are configured.
+pub const NATIVE_MINT: Pubkey = anchor_spl::token::spl_token::native_mint::ID;
+
+impl FeeRule {
+    pub fn validate(&self) -> Result<()> {
+        require!(self.bps <= 10_000, ShieldError::InvalidFeeRule);
+        require!(self.max_fee == 0 || self.max_fee >= self.min_fee, ShieldError::InvalidFeeRule);
+        Ok(())
+    }
+
+    pub fn fee_for(&self, amount: u64) -> u64 {
+        let fee = (amount as u128 * self.bps as u128 / 10_000) as u64;
+        let fee = fee.max(self.min_fee);
+        if self.max_fee == 0 {
+            fee
+        } else {
+            fee.min(self.max_fee)
+        }
+    }
+}
+
+impl FeeSchedule {
+    /// The mint's override for `operation` if it has one, else the base rule.
+    pub fn rule(&self, operation: FeeOperation, mint: &Pubkey) -> FeeRule {
+        self.overrides
+            .iter()
+            .find(|entry| entry.operation == operation && entry.mint == *mint)
+            .map(|entry| entry.rule)
+            .unwrap_or(self.rules[operation as usize])
+    }
+
+    /// Fee owed for `operation` on `amount` of `mint`, announced with `FeeCharged`.
+    pub fn charge(&self, operation: FeeOperation, mint: Pubkey, amount: u64) -> u64 {
+        let fee = self.rule(operation, &mint).fee_for(amount);
+        emit!(FeeCharged {
+            operation,
+            mint,
+            amount,
+            fee,
+        });
+        fee
+    }
+
+    /// Apply a matured `SetFeeRule` proposal.
+    pub fn set_rule(&mut self, operation: FeeOperation, mint: Option<Pubkey>, rule: Option<FeeRule>) -> Result<()> {
+        if let Some(rule) = rule {
+            rule.validate()?;
+        }
+        let Some(mint) = mint else {
+            self.rules[operation as usize] = rule.ok_or(error!(ShieldError::InvalidFeeRule))?;
+            return Ok(());
+        };
+
+        let existing = self
+            .overrides
+            .iter()
+            .position(|entry| entry.operation == operation && entry.mint == mint);
+        match (existing, rule) {
+            (Some(index), Some(rule)) => self.overrides[index].rule = rule,
+            (Some(index), None) => {
+                self.overrides.remove(index);
+            }
+            (None, Some(rule)) => {
+                require!(self.overrides.len() < MAX_FEE_OVERRIDES, ShieldError::CapacityExceeded);
+                self.overrides.push(MintFeeOverride { mint, operation, rule });
+            }
+            (None, None) => {}
+        }
+        Ok(())
+    }
+}
+
+/// What the recipient of a withdrawn note receives once the protocol and relayer fees are paid.
+pub fn recipient_share(amount: u64, protocol_fee: u64, relayer_fee: u64) -> Result<u64> {
+    let fees = protocol_fee
+        .checked_add(relayer_fee)
+        .ok_or(ShieldError::FeeExceedsAmount)?;
+    require!(fees < amount, ShieldError::FeeExceedsAmount);
+    Ok(amount - fees)
+}
+
+/// Swap fee on a shielded swap's input. The fee stays in the input asset's vault,
+/// recorded on its `VaultLedger` until `collect_pool_fees`.
+pub fn charge_pool_swap_fee(schedule: &FeeSchedule, input_mint: Pubkey, input_amount: u64) -> Result<u64> {
+    let fee = schedule.charge(FeeOperation::Swap, input_mint, input_amount);
+    require!(fee < input_amount, ShieldError::FeeExceedsAmount);
+    Ok(fee)
+}
+
+/// Move `fee` lamports into the SOL fee vault. `signer_seeds` is empty when `from` signed the transaction.
+pub fn collect_sol_fee<'info>(
+    from: &AccountInfo<'info>,
+    sol_fee_vault: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    fee: u64,
+) -> Result<()> {
+    if fee == 0 {
+        return Ok(());
+    }
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Transfer {
+                from: from.clone(),
+                to: sol_fee_vault.clone(),
+            },
+            signer_seeds,
+        ),
+        fee,
+    )
+}
+
+/// Move `fee` tokens into the mint's fee vault. `signer_seeds` is empty when `authority` signed the transaction.
+pub fn collect_token_fee<'info>(
+    transfer: &CheckedTransfer<'_, 'info>,
+    from: &AccountInfo<'info>,
+    fee_vault: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    fee: u64,
+) -> Result<()> {
+    if fee == 0 {
+        return Ok(());
+    }
+    transfer.send(from.clone(), fee_vault.clone(), authority.clone(), signer_seeds, fee)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule() -> FeeSchedule {
+        FeeSchedule {
+            rules: [FeeRule::default(); FeeOperation::COUNT],
+            overrides: Vec::new(),
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn fee_is_clamped_to_min_and_max() {
+        let rule = FeeRule {
+            bps: 30,
+            min_fee: 1_000,
+            max_fee: 50_000,
+        };
+        assert_eq!(rule.fee_for(10_000), 1_000);
+        assert_eq!(rule.fee_for(1_000_000), 3_000);
+        assert_eq!(rule.fee_for(u64::MAX), 50_000);
+
+        let uncapped = FeeRule { max_fee: 0, ..rule };
+        assert_eq!(uncapped.fee_for(u64::MAX), (u64::MAX as u128 * 30 / 10_000) as u64);
+
+        assert!(FeeRule { bps: 10_001, ..rule }.validate().is_err());
+        assert!(FeeRule { max_fee: 999, ..rule }.validate().is_err());
+    }
+
+    #[test]
+    fn mint_override_replaces_base_rule_until_removed() {
+        let mut schedule = schedule();
+        let mint = Pubkey::new_unique();
+        let base = FeeRule {
+            bps: 10,
+            ..FeeRule::default()
+        };
+        let special = FeeRule {
+            bps: 50,
+            ..FeeRule::default()
+        };
+        schedule.set_rule(FeeOperation::Withdraw, None, Some(base)).unwrap();
+        schedule.set_rule(FeeOperation::Withdraw, Some(mint), Some(special)).unwrap();
+
+        assert_eq!(schedule.rule(FeeOperation::Withdraw, &mint), special);
+        assert_eq!(schedule.rule(FeeOperation::Withdraw, &NATIVE_MINT), base);
+        assert_eq!(schedule.rule(FeeOperation::Deposit, &mint), FeeRule::default());
+
+        schedule.set_rule(FeeOperation::Withdraw, Some(mint), None).unwrap();
+        assert_eq!(schedule.rule(FeeOperation::Withdraw, &mint), base);
+        assert!(schedule.set_rule(FeeOperation::Withdraw, None, None).is_err());
+    }
+
+    #[test]
+    fn recipient_keeps_the_rest_after_both_fees() {
+        assert_eq!(recipient_share(1_000, 30, 50).unwrap(), 920);
+        assert!(recipient_share(1_000, 950, 50).is_err());
+        assert!(recipient_share(1_000, 1, u64::MAX).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::ShieldError,
+    events::ProposalQueued,
+    state::{GovernanceAction, GovernanceQueue, Proposal, VerifierAccount, MAX_PENDING_PROPOSALS},
+};
+
+impl GovernanceQueue {
+    /// Queue `action` to become executable `delay_seconds` after `now`.
+    pub fn queue(&mut self, action: GovernanceAction, now: i64) -> Result<u64> {
+        require!(self.proposals.len() < MAX_PENDING_PROPOSALS, ShieldError::CapacityExceeded);
+        let id = self.next_proposal_id;
+        let eta = now.checked_add(self.delay_seconds).ok_or(ShieldError::InvalidAmount)?;
+        self.next_proposal_id += 1;
+        self.proposals.push(Proposal {
+            id,
+            eta,
+            action: action.clone(),
+        });
+        emit!(ProposalQueued { id, eta, action });
+        Ok(id)
+    }
+
+    /// Remove and return proposal `id` if its timelock has expired.
+    pub fn take_ready(&mut self, id: u64, now: i64) -> Result<Proposal> {
+        let index = self.position(id)?;
+        require!(now >= self.proposals[index].eta, ShieldError::ProposalNotReady);
+        Ok(self.proposals.remove(index))
+    }
+
+    pub fn cancel(&mut self, id: u64) -> Result<Proposal> {
+        let index = self.position(id)?;
+        Ok(self.proposals.remove(index))
+    }
+
+    fn position(&self, id: u64) -> Result<usize> {
+        self.proposals
+            .iter()
+            .position(|proposal| proposal.id == id)
+            .ok_or_else(|| error!(ShieldError::ProposalNotFound))
+    }
+}
+
+/// Queue activation of the finalized `verifier` as the active key of its circuit.
+pub fn queue_verifier_activation(queue: &mut GovernanceQueue, verifier: &VerifierAccount) -> Result<u64> {
+    queue.queue(
+        GovernanceAction::ActivateVerifier {
+            circuit_id: verifier.circuit()?,
+            version: verifier.version,
+            key_hash: verifier.key_hash,
+        },
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proposal_executes_only_after_delay() {
+        let mut queue = GovernanceQueue {
+            delay_seconds: 100,
+            next_proposal_id: 0,
+            proposals: Vec::new(),
+            bump: 255,
+        };
+        let id = queue
+            .queue(
+                GovernanceAction::SetFee {
+                    shield_fee_bps: 25,
+                    priority_fee_bps: 50,
+                },
+                1_000,
+            )
+            .unwrap();
+
+        assert!(queue.take_ready(id, 1_099).is_err());
+        assert_eq!(queue.take_ready(id, 1_100).unwrap().eta, 1_100);
+        assert!(queue.take_ready(id, 1_100).is_err());
+    }
+}
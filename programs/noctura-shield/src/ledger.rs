@@ -0,0 +1,145 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::ShieldError, state::VaultLedger};
+
+impl VaultLedger {
+    /// Value owed out of the vault: unspent notes. Fees are the protocol's own and are
+    /// not owed to anyone; the pool's reserves live in its own vaults.
+    pub fn liabilities(&self) -> u128 {
+        self.shielded_deposits - self.shielded_withdrawals
+    }
+
+    pub fn record_deposit(&mut self, amount: u64) -> Result<()> {
+        self.shielded_deposits = self
+            .shielded_deposits
+            .checked_add(amount as u128)
+            .ok_or(ShieldError::CapacityExceeded)?;
+        Ok(())
+    }
+
+    /// Fails when more leaves the notes than ever entered them.
+    pub fn record_withdrawal(&mut self, amount: u64) -> Result<()> {
+        let withdrawals = self.shielded_withdrawals + amount as u128;
+        require!(withdrawals <= self.shielded_deposits, ShieldError::VaultInsolvent);
+        self.shielded_withdrawals = withdrawals;
+        Ok(())
+    }
+
+    pub fn record_fee(&mut self, fee: u64) -> Result<()> {
+        self.fees = self.fees.checked_add(fee).ok_or(ShieldError::CapacityExceeded)?;
+        Ok(())
+    }
+
+    /// Take all accrued fees, for moving them out of the vault.
+    pub fn take_fees(&mut self) -> u64 {
+        std::mem::take(&mut self.fees)
+    }
+
+    /// What a vault holding `vault_balance` has beyond its liabilities.
+    pub fn surplus(&self, vault_balance: u64) -> u64 {
+        (vault_balance as u128).saturating_sub(self.liabilities()) as u64
+    }
+
+    /// Check the vault, after the instruction's transfers, still covers what it owes.
+    pub fn require_solvent(&self, vault_balance: u64) -> Result<()> {
+        require!(vault_balance as u128 >= self.liabilities(), ShieldError::VaultInsolvent);
+        Ok(())
+    }
+}
+
+/// Lamports of the SOL vault its ledger counts: those above `rent_exempt`, the minimum that
+/// keeps the vault open, which is never owed to notes and never swept.
+pub fn sol_vault_balance(lamports: u64, rent_exempt: u64) -> u64 {
+    lamports.saturating_sub(rent_exempt)
+}
+
+/// [`sol_vault_balance`] of `vault` under the current rent.
+pub fn sol_vault_holdings(vault: &AccountInfo) -> Result<u64> {
+    Ok(sol_vault_balance(vault.lamports(), Rent::get()?.minimum_balance(0)))
+}
+
+/// Start a ledger with `opening_balance`, the vault's holdings from before it was tracked, owed to notes.
+pub fn open_ledger(ledger: &mut VaultLedger, mint: Pubkey, opening_balance: u64, bump: u8) {
+    ledger.mint = mint;
+    ledger.shielded_deposits = opening_balance as u128;
+    ledger.shielded_withdrawals = 0;
+    ledger.fees = 0;
+    ledger.bump = bump;
+}
+
+/// Ledger side of a shielded swap. `input_amount` leaves the input asset's notes: `fee`
+/// of it stays in the vault for the fee vault and the rest moves to the pool's vault;
+/// `output_amount` arrives from the pool's vault of the output asset for a new note.
+pub fn record_shielded_swap(
+    input: &mut VaultLedger,
+    output: &mut VaultLedger,
+    input_amount: u64,
+    fee: u64,
+    output_amount: u64,
+) -> Result<()> {
+    input.record_withdrawal(input_amount)?;
+    input.record_fee(fee)?;
+    output.record_deposit(output_amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger() -> VaultLedger {
+        VaultLedger {
+            mint: Pubkey::new_unique(),
+            shielded_deposits: 0,
+            shielded_withdrawals: 0,
+            fees: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn notes_must_stay_covered() {
+        let mut sol = ledger();
+        let mut noc = ledger();
+        sol.record_deposit(1_000).unwrap();
+
+        // The vault keeps the fee and sends the other 396 to the pool
+        record_shielded_swap(&mut sol, &mut noc, 400, 4, 1_900).unwrap();
+        assert_eq!(sol.liabilities(), 600);
+        assert_eq!(noc.liabilities(), 1_900);
+        sol.require_solvent(604).unwrap();
+        assert_eq!(sol.surplus(604), 4);
+        assert_eq!(sol.take_fees(), 4);
+        sol.require_solvent(600).unwrap();
+        assert!(sol.require_solvent(599).is_err());
+
+        // Swap output is owed to its note, but never counts as deposited input value
+        assert!(sol.record_withdrawal(601).is_err());
+        noc.require_solvent(1_900).unwrap();
+    }
+
+    #[test]
+    fn sol_ledger_leaves_out_the_rent_exempt_reserve() {
+        const RENT_EXEMPT: u64 = 890_880;
+        // 5_000 lamports of notes predate the ledger; init adds the reserve on top
+        let mut sol = ledger();
+        open_ledger(&mut sol, Pubkey::default(), 5_000, 255);
+        let mut lamports = 5_000 + RENT_EXEMPT;
+        sol.require_solvent(sol_vault_balance(lamports, RENT_EXEMPT)).unwrap();
+        assert_eq!(sol.surplus(sol_vault_balance(lamports, RENT_EXEMPT)), 0);
+
+        sol.record_deposit(1_000).unwrap();
+        sol.record_fee(10).unwrap();
+        lamports += 1_010;
+        assert_eq!(sol.surplus(sol_vault_balance(lamports, RENT_EXEMPT)), 10);
+
+        // The reserve never covers notes
+        assert!(sol.require_solvent(sol_vault_balance(6_000 + RENT_EXEMPT - 1, RENT_EXEMPT)).is_err());
+
+        // Paying out every note and sweeping the surplus leaves exactly the reserve
+        sol.record_withdrawal(6_000).unwrap();
+        lamports -= 6_000;
+        lamports -= sol.surplus(sol_vault_balance(lamports, RENT_EXEMPT));
+        sol.require_solvent(sol_vault_balance(lamports, RENT_EXEMPT)).unwrap();
+        assert_eq!(lamports, RENT_EXEMPT);
+    }
+}
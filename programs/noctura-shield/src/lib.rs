@@ -1,33 +1,59 @@
 #![allow(unexpected_cfgs)]
-use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_lang::{prelude::*, Discriminator};
+use anchor_spl::token_interface::{self, CloseAccount, Mint, TokenAccount, TokenInterface};
 
+pub mod assets;
+pub mod bn254;
+#[cfg(feature = "devnet")]
+pub mod devnet;
 pub mod errors;
 pub mod events;
+pub mod fees;
+pub mod governance;
+pub mod ledger;
 pub mod merkle;
+pub mod pool;
+pub mod public_inputs;
 pub mod state;
+pub mod token_vault;
 pub mod utils;
 pub mod verifier;
 
+use assets::*;
 use errors::*;
 use events::*;
+use fees::*;
+use governance::*;
+use ledger::*;
+use merkle::*;
+use pool::*;
+use public_inputs::*;
 use state::*;
 use utils::*;
+use token_vault::*;
 use verifier::*;
 
 declare_id!("3KN2qrmEtPyk9WGu9jJSzLerxU8AUXAy8Dp6bqw5APDz");
 
-const GLOBAL_STATE_SEED: &[u8] = b"global-state";
+pub(crate) const GLOBAL_STATE_SEED: &[u8] = b"global-state";
 const TREE_SEED: &[u8] = b"merkle-tree";
-const NULLIFIER_SEED: &[u8] = b"nullifiers";
+pub(crate) const NULLIFIER_SEED: &[u8] = b"nullifiers";
+const NULLIFIER_RECORD_SEED: &[u8] = b"nullifier";
 const VERIFIER_SEED: &[u8] = b"verifier";
-const WITHDRAW_VERIFIER_SEED: &[u8] = b"withdraw-verifier";
-const TRANSFER_VERIFIER_SEED: &[u8] = b"transfer-verifier";
-const PARTIAL_WITHDRAW_VERIFIER_SEED: &[u8] = b"partial-withdraw-verifier";
 const VAULT_AUTHORITY_SEED: &[u8] = b"vault-authority";
 const LEGACY_VAULT_AUTHORITY_SEED: &[u8] = b"vault-authority"; // For vaults without mint suffix
 const VAULT_TOKEN_SEED: &[u8] = b"vault-token";
 const SOL_VAULT_SEED: &[u8] = b"sol-vault";
+pub(crate) const ROLE_SEED: &[u8] = b"role";
+const ADMIN_CONFIG_SEED: &[u8] = b"admin-config";
+const GOVERNANCE_SEED: &[u8] = b"governance";
+const FEE_SCHEDULE_SEED: &[u8] = b"fee-schedule";
+const FEE_VAULT_SEED: &[u8] = b"fee-vault";
+const FEE_VAULT_AUTHORITY_SEED: &[u8] = b"fee-vault-authority";
+const SOL_FEE_VAULT_SEED: &[u8] = b"sol-fee-vault";
+const ASSET_SEED: &[u8] = b"asset";
+const MINT_FIELD_SEED: &[u8] = b"mint-field";
+const VAULT_LEDGER_SEED: &[u8] = b"vault-ledger";
 
 #[program]
 pub mod noctura_shield {
@@ -36,6 +62,7 @@ pub mod noctura_shield {
     pub fn initialize(
         ctx: Context<Initialize>,
         tree_height: u8,
+        root_history_len: u32,
         fee_collector: Pubkey,
         shield_fee_bps: u16,
         priority_fee_bps: u16,
@@ -49,221 +76,743 @@ pub mod noctura_shield {
         global.priority_fee_bps = priority_fee_bps.max(shield_fee_bps);
         global.tree = ctx.accounts.merkle_tree.key();
         global.nullifier_set = ctx.accounts.nullifier_set.key();
-        global.verifier = ctx.accounts.verifier.key();
+        global.verifier_versions = [0; CircuitId::COUNT];
         global.bump = ctx.bumps.global_state;
+        global.pending_admin = Pubkey::default();
 
-        ctx.accounts.merkle_tree.initialize(tree_height)?;
+        let admin_config = &mut ctx.accounts.admin_config;
+        admin_config.threshold = 0;
+        admin_config.signers = Vec::new();
+        admin_config.bump = ctx.bumps.admin_config;
+
+        ctx.accounts.merkle_tree.load_init()?.initialize(tree_height, root_history_len)?;
         ctx.accounts.nullifier_set.nullifiers = Vec::new();
-        ctx.accounts.verifier.verifying_key = Vec::new();
 
         Ok(())
     }
 
-    pub fn set_verifier(ctx: Context<SetVerifier>, verifying_key: Vec<u8>) -> Result<()> {
-        require!(ctx.accounts.admin.key() == ctx.accounts.global_state.admin, ShieldError::Unauthorized);
-        validate_verifier_key_blob(&verifying_key)?;
-        ctx.accounts.verifier.verifying_key = verifying_key;
+    /// Rewrite a `GlobalState` created before the `CircuitId` registry and the admin handover
+    /// into the current layout, growing the account to fit, and create the `AdminConfig` that
+    /// only `initialize` used to. Signed by the admin recorded in it; the admin pays the rent.
+    /// One-shot: an account already at the current size is refused.
+    pub fn migrate_global_state(ctx: Context<MigrateGlobalState>) -> Result<()> {
+        let info = ctx.accounts.global_state.to_account_info();
+        let space = GlobalState::LEN + 8;
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() < space && data.len() >= 8 && data[..8] == GlobalState::DISCRIMINATOR,
+                ShieldError::AccountAlreadyMigrated
+            );
+            LegacyGlobalState::deserialize(&mut &data[8..])?
+        };
+        require_keys_eq!(legacy.admin, ctx.accounts.admin.key(), ShieldError::Unauthorized);
+
+        let top_up = Rent::get()?.minimum_balance(space).saturating_sub(info.lamports());
+        if top_up > 0 {
+            transfer_lamports(
+                &ctx.accounts.admin.to_account_info(),
+                &info,
+                &ctx.accounts.system_program.to_account_info(),
+                &[],
+                top_up,
+            )?;
+        }
+        info.realloc(space, true)?;
+
+        let mut global = legacy.upgrade();
+        global.bump = ctx.bumps.global_state;
+        global.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        let admin_config = &mut ctx.accounts.admin_config;
+        admin_config.threshold = 0;
+        admin_config.signers = Vec::new();
+        admin_config.bump = ctx.bumps.admin_config;
+        msg!("Global state migrated to {} bytes", space);
         Ok(())
     }
 
-    pub fn set_withdraw_verifier(ctx: Context<SetWithdrawVerifier>, verifying_key: Vec<u8>) -> Result<()> {
-        require!(ctx.accounts.admin.key() == ctx.accounts.global_state.admin, ShieldError::Unauthorized);
-        validate_verifier_key_blob(&verifying_key)?;
-        ctx.accounts.withdraw_verifier.verifying_key = verifying_key;
+    /// Rewrite a Merkle tree created before the zero-copy layout as an empty tree of
+    /// `tree_height`, growing the account to fit. The legacy keccak tree stored no leaves and
+    /// its roots never matched a circuit root, so there is nothing to carry over. Built with
+    /// `legacy-keccak-tree`, the legacy tree is checked against its roots and its last root logged first.
+    /// Super-admin only; the admin pays the rent. An account already at the current size is refused.
+    pub fn migrate_merkle_tree(ctx: Context<MigrateMerkleTree>, tree_height: u8, root_history_len: u32) -> Result<()> {
+        require_admin_approval(&ctx.accounts.admin_config, &ctx.accounts.admin.key(), ctx.remaining_accounts)?;
+        let info = ctx.accounts.merkle_tree.to_account_info();
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() < MerkleTreeAccount::LEN
+                    && data.len() >= 8
+                    && data[..8] == MerkleTreeAccount::DISCRIMINATOR,
+                ShieldError::AccountAlreadyMigrated
+            );
+            LegacyMerkleTreeAccount::deserialize(&mut &data[8..])?
+        };
+        #[cfg(feature = "legacy-keccak-tree")]
+        {
+            legacy.require_consistent()?;
+            if let Some(root) = legacy.latest_root() {
+                solana_program::log::sol_log_data(&[b"legacy.root", &root]);
+            }
+        }
+
+        let top_up = Rent::get()?.minimum_balance(MerkleTreeAccount::LEN).saturating_sub(info.lamports());
+        if top_up > 0 {
+            transfer_lamports(
+                &ctx.accounts.admin.to_account_info(),
+                &info,
+                &ctx.accounts.system_program.to_account_info(),
+                &[],
+                top_up,
+            )?;
+        }
+        info.realloc(MerkleTreeAccount::LEN, true)?;
+
+        let mut data = info.try_borrow_mut_data()?;
+        data[8..].fill(0);
+        bytemuck::from_bytes_mut::<MerkleTreeAccount>(&mut data[8..]).initialize(tree_height, root_history_len)?;
+        msg!(
+            "Merkle tree migrated to {} bytes; {} legacy leaves dropped",
+            MerkleTreeAccount::LEN,
+            legacy.current_index
+        );
         Ok(())
     }
 
-    pub fn set_transfer_verifier(ctx: Context<SetTransferVerifier>, verifying_key: Vec<u8>) -> Result<()> {
-        require!(ctx.accounts.admin.key() == ctx.accounts.global_state.admin, ShieldError::Unauthorized);
-        validate_verifier_key_blob(&verifying_key)?;
-        ctx.accounts.transfer_verifier.verifying_key = verifying_key;
+    /// Create an empty verifier for `circuit_id` at `version`, ready for chunked upload.
+    /// Versions are never overwritten; a new key gets a new version.
+    pub fn init_verifier(ctx: Context<InitVerifier>, circuit_id: CircuitId, version: u16) -> Result<()> {
+        require!(version > 0, ShieldError::InvalidVerifierKey);
+        let mut verifier = ctx.accounts.verifier.load_init()?;
+        verifier.circuit_id = circuit_id as u8;
+        verifier.version = version;
+        msg!("Verifier {:?} v{} initialized for upload", circuit_id, version);
         Ok(())
     }
 
-    pub fn set_partial_withdraw_verifier(ctx: Context<SetPartialWithdrawVerifier>, verifying_key: Vec<u8>) -> Result<()> {
-        require!(ctx.accounts.admin.key() == ctx.accounts.global_state.admin, ShieldError::Unauthorized);
-        validate_verifier_key_blob(&verifying_key)?;
-        ctx.accounts.partial_withdraw_verifier.verifying_key = verifying_key;
+    /// Append a chunk of the serialized verifying key
+    pub fn append_verifier_chunk(
+        ctx: Context<AppendVerifierChunk>,
+        _circuit_id: CircuitId,
+        _version: u16,
+        chunk: Vec<u8>,
+    ) -> Result<()> {
+        let mut verifier = ctx.accounts.verifier.load_mut()?;
+        require!(!verifier.is_finalized(), ShieldError::VerifierFinalized);
+        verifier.append(&chunk)?;
+        msg!("Appended {} bytes to verifier, total: {}", chunk.len(), verifier.key_len);
         Ok(())
     }
 
-    pub fn set_swap_verifier(ctx: Context<SetSwapVerifier>, verifying_key: Vec<u8>) -> Result<()> {
-        validate_verifier_key_blob(&verifying_key)?;
-        ctx.accounts.swap_verifier.verifying_key = verifying_key;
-        msg!("Swap verifier set");
+    /// Check the next of the uploaded key's beta, gamma and delta for G2 subgroup membership.
+    /// Each check is a software scalar multiplication, so they take one instruction apiece;
+    /// call this three times after the last chunk and before `finalize_verifier`.
+    pub fn check_verifier_subgroup(
+        ctx: Context<CheckVerifierSubgroup>,
+        _circuit_id: CircuitId,
+        _version: u16,
+    ) -> Result<()> {
+        let mut verifier = ctx.accounts.verifier.load_mut()?;
+        require!(!verifier.is_finalized(), ShieldError::VerifierFinalized);
+        let index = verifier.g2_checked;
+        require!(index < VERIFIER_G2_POINTS, ShieldError::InvalidVerifierKey);
+        check_verifier_g2_point(verifier.key(), index as usize)?;
+        verifier.g2_checked = index + 1;
+        msg!("Verifier G2 point {} is in the subgroup", index);
         Ok(())
     }
 
-    /// Initialize swap verifier for chunked upload (use when key is too large for single tx)
-    pub fn init_swap_verifier_chunked(ctx: Context<InitSwapVerifierChunked>) -> Result<()> {
-        ctx.accounts.swap_verifier.verifying_key = Vec::new();
-        msg!("Swap verifier initialized for chunked upload");
+    /// Validate the uploaded key, lock it and queue its activation through governance.
+    /// `expected_hash` is the sha256 of the packed key the uploader meant to send.
+    /// The key must have one IC point per public input of the circuit's layout, and its
+    /// G2 points must all have passed `check_verifier_subgroup`. Every key point is then
+    /// checked on-curve, and the stored key is rewritten
+    /// with beta, gamma and delta negated, ready for the pairing. `key_hash` covers the
+    /// key as uploaded: to recompute it from the account, negate those three points back.
+    pub fn finalize_verifier(
+        ctx: Context<FinalizeVerifier>,
+        _circuit_id: CircuitId,
+        _version: u16,
+        expected_hash: [u8; 32],
+    ) -> Result<()> {
+        let mut verifier = ctx.accounts.verifier.load_mut()?;
+        require!(!verifier.is_finalized(), ShieldError::VerifierFinalized);
+        require!(
+            verifier.g2_checked == VERIFIER_G2_POINTS,
+            ShieldError::VerifierSubgroupUnchecked
+        );
+        let key_hash = anchor_lang::solana_program::hash::hash(verifier.key()).to_bytes();
+        require!(key_hash == expected_hash, ShieldError::VerifierHashMismatch);
+        prepare_verifier_key(verifier.key_mut())?;
+        require_public_input_count(verifier.circuit()?, verifier.key())?;
+        verifier.key_hash = key_hash;
+        verifier.finalized = 1;
+        queue_verifier_activation(&mut ctx.accounts.governance_queue, &verifier)?;
+        msg!("Verifier finalized with {} bytes", verifier.key_len);
         Ok(())
     }
 
-    /// Append chunk to swap verifier
-    pub fn append_swap_verifier_chunk(ctx: Context<AppendSwapVerifierChunk>, chunk: Vec<u8>) -> Result<()> {
-        let chunk_len = chunk.len();
-        ctx.accounts.swap_verifier.verifying_key.extend(chunk);
-        msg!("Appended {} bytes to swap verifier, total: {}", chunk_len, ctx.accounts.swap_verifier.verifying_key.len());
+    /// Shielded consolidation: merge multiple notes into one using consolidate circuit
+    pub fn shielded_consolidate<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ShieldedConsolidate<'info>>,
+        input_nullifiers: Vec<[u8; 32]>,
+        output_commitment: [u8; 32],
+        merkle_root: [u8; 32],
+        proof: Vec<u8>,
+        public_inputs: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require!(!input_nullifiers.is_empty(), ShieldError::InvalidAmount);
+        load_tree(&ctx.accounts.merkle_tree)?.require_known_root(&field_from_le(&merkle_root))?;
+        let layout = PublicInputLayout::Consolidate {
+            merkle_root: &merkle_root,
+            output_commitment: &output_commitment,
+            nullifiers: &input_nullifiers,
+        };
+        verify_groth16(&*ctx.accounts.consolidate_verifier.load()?, &proof, &public_inputs, &layout)?;
+
+        let nullifier_count = input_nullifiers.len();
+
+        // One NullifierRecord PDA per input nullifier, in order, as remaining accounts
+        require!(
+            ctx.remaining_accounts.len() == input_nullifiers.len(),
+            ShieldError::InvalidNullifierAccount
+        );
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        for (nullifier, record) in input_nullifiers.into_iter().zip(ctx.remaining_accounts.iter()) {
+            consume_nullifier(&ctx.accounts.nullifier_set, record, &payer, &system_program, nullifier)?;
+            emit!(NullifierConsumed { nullifier });
+        }
+
+        let _root = load_tree_mut(&ctx.accounts.merkle_tree)?.append_commitment(&output_commitment)?;
+        msg!("Consolidated {} notes into 1", nullifier_count);
+
         Ok(())
     }
 
-    /// Finalize swap verifier (validate the complete key)
-    pub fn finalize_swap_verifier(ctx: Context<FinalizeSwapVerifier>) -> Result<()> {
-        validate_verifier_key_blob(&ctx.accounts.swap_verifier.verifying_key)?;
-        msg!("Swap verifier finalized with {} bytes", ctx.accounts.swap_verifier.verifying_key.len());
+    /// Super-admin function to nominate a new admin; takes effect once they call `accept_admin`
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        require_admin_approval(&ctx.accounts.admin_config, &ctx.accounts.admin.key(), ctx.remaining_accounts)?;
+        ctx.accounts.global_state.pending_admin = new_admin;
+        emit!(AdminProposed {
+            admin: ctx.accounts.admin.key(),
+            pending_admin: new_admin,
+        });
         Ok(())
     }
 
-    /// Set swap V2 verifier (for partial swaps with change)
-    pub fn set_swap_v2_verifier(ctx: Context<SetSwapV2Verifier>, verifying_key: Vec<u8>) -> Result<()> {
-        validate_verifier_key_blob(&verifying_key)?;
-        ctx.accounts.swap_v2_verifier.verifying_key = verifying_key;
-        msg!("Swap V2 verifier set");
+    /// Completes the handover started by `propose_admin`; must be signed by the pending admin
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let global = &mut ctx.accounts.global_state;
+        let previous_admin = global.admin;
+        global.admin = ctx.accounts.pending_admin.key();
+        global.pending_admin = Pubkey::default();
+        emit!(AdminAccepted {
+            previous_admin,
+            admin: global.admin,
+        });
         Ok(())
     }
 
-    /// Initialize swap V2 verifier for chunked upload
-    pub fn init_swap_v2_verifier_chunked(ctx: Context<InitSwapV2VerifierChunked>) -> Result<()> {
-        ctx.accounts.swap_v2_verifier.verifying_key = Vec::new();
-        msg!("Swap V2 verifier initialized for chunked upload");
+    /// Super-admin function to require `threshold` of `signers` to co-sign super-admin actions.
+    /// A threshold of 0 with no signers returns to single-key admin.
+    pub fn set_admin_multisig(ctx: Context<SetAdminMultisig>, signers: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        require_admin_approval(&ctx.accounts.admin_config, &ctx.accounts.admin.key(), ctx.remaining_accounts)?;
+        require!(signers.len() <= MAX_ADMIN_SIGNERS, ShieldError::CapacityExceeded);
+        require!(
+            (threshold == 0 && signers.is_empty()) || (threshold > 0 && threshold as usize <= signers.len()),
+            ShieldError::InvalidThreshold
+        );
+        for (i, signer) in signers.iter().enumerate() {
+            require!(!signers[..i].contains(signer), ShieldError::InvalidThreshold);
+        }
+
+        let admin_config = &mut ctx.accounts.admin_config;
+        admin_config.threshold = threshold;
+        admin_config.signers = signers;
+        emit!(AdminMultisigUpdated {
+            threshold,
+            signer_count: admin_config.signers.len() as u8,
+        });
         Ok(())
     }
 
-    /// Append chunk to swap V2 verifier
-    pub fn append_swap_v2_verifier_chunk(ctx: Context<AppendSwapV2VerifierChunk>, chunk: Vec<u8>) -> Result<()> {
-        let chunk_len = chunk.len();
-        ctx.accounts.swap_v2_verifier.verifying_key.extend(chunk);
-        msg!("Appended {} bytes to swap V2 verifier, total: {}", chunk_len, ctx.accounts.swap_v2_verifier.verifying_key.len());
+    /// Super-admin function to grant `role` to `holder`, creating its role account if needed
+    pub fn grant_role(ctx: Context<GrantRole>, holder: Pubkey, role: Role) -> Result<()> {
+        require_admin_approval(&ctx.accounts.admin_config, &ctx.accounts.admin.key(), ctx.remaining_accounts)?;
+        let role_account = &mut ctx.accounts.role_account;
+        role_account.holder = holder;
+        role_account.roles |= role.mask();
+        role_account.bump = ctx.bumps.role_account;
+        emit!(RoleGranted { holder, role });
         Ok(())
     }
 
-    /// Finalize swap V2 verifier
-    pub fn finalize_swap_v2_verifier(ctx: Context<FinalizeSwapV2Verifier>) -> Result<()> {
-        validate_verifier_key_blob(&ctx.accounts.swap_v2_verifier.verifying_key)?;
-        msg!("Swap V2 verifier finalized with {} bytes", ctx.accounts.swap_v2_verifier.verifying_key.len());
+    /// Super-admin function to revoke `role` from `holder`
+    pub fn revoke_role(ctx: Context<RevokeRole>, holder: Pubkey, role: Role) -> Result<()> {
+        require_admin_approval(&ctx.accounts.admin_config, &ctx.accounts.admin.key(), ctx.remaining_accounts)?;
+        ctx.accounts.role_account.roles &= !role.mask();
+        emit!(RoleRevoked { holder, role });
         Ok(())
     }
 
-    /// Initialize consolidate verifier for chunked upload
-    pub fn init_consolidate_verifier_chunked(ctx: Context<InitConsolidateVerifierChunked>) -> Result<()> {
-        ctx.accounts.consolidate_verifier.verifying_key = Vec::new();
-        msg!("Consolidate verifier initialized for chunked upload");
+    /// Admin function to queue a shield fee change (in basis points)
+    pub fn set_fee(ctx: Context<SetFee>, shield_fee_bps: u16, priority_fee_bps: u16) -> Result<()> {
+        ctx.accounts.governance_queue.queue(
+            GovernanceAction::SetFee {
+                shield_fee_bps,
+                priority_fee_bps,
+            },
+            Clock::get()?.unix_timestamp,
+        )?;
         Ok(())
     }
 
-    /// Append chunk to consolidate verifier
-    pub fn append_consolidate_verifier_chunk(ctx: Context<AppendConsolidateVerifierChunk>, chunk: Vec<u8>) -> Result<()> {
-        let chunk_len = chunk.len();
-        ctx.accounts.consolidate_verifier.verifying_key.extend(chunk);
-        msg!("Appended {} bytes to consolidate verifier, total: {}", chunk_len, ctx.accounts.consolidate_verifier.verifying_key.len());
+    /// Super-admin function to create the governance queue with its initial timelock
+    pub fn initialize_governance(ctx: Context<InitializeGovernance>, delay_seconds: i64) -> Result<()> {
+        require_admin_approval(&ctx.accounts.admin_config, &ctx.accounts.admin.key(), ctx.remaining_accounts)?;
+        require!(delay_seconds >= 0, ShieldError::InvalidAmount);
+        let queue = &mut ctx.accounts.governance_queue;
+        queue.delay_seconds = delay_seconds;
+        queue.next_proposal_id = 0;
+        queue.proposals = Vec::new();
+        queue.bump = ctx.bumps.governance_queue;
         Ok(())
     }
 
-    /// Finalize consolidate verifier
-    pub fn finalize_consolidate_verifier(ctx: Context<FinalizeConsolidateVerifier>) -> Result<()> {
-        validate_verifier_key_blob(&ctx.accounts.consolidate_verifier.verifying_key)?;
-        msg!("Consolidate verifier finalized with {} bytes", ctx.accounts.consolidate_verifier.verifying_key.len());
+    /// Super-admin function to queue a change of the governance timelock itself
+    pub fn propose_governance_delay(ctx: Context<ProposeGovernanceDelay>, delay_seconds: i64) -> Result<()> {
+        require_admin_approval(&ctx.accounts.admin_config, &ctx.accounts.admin.key(), ctx.remaining_accounts)?;
+        require!(delay_seconds >= 0, ShieldError::InvalidAmount);
+        ctx.accounts
+            .governance_queue
+            .queue(GovernanceAction::SetDelay { delay_seconds }, Clock::get()?.unix_timestamp)?;
         Ok(())
     }
 
-    /// Shielded consolidation: merge multiple notes into one using consolidate circuit
-    pub fn shielded_consolidate(
-        ctx: Context<ShieldedConsolidate>,
-        input_nullifiers: Vec<[u8; 32]>,
-        output_commitment: [u8; 32],
-        proof: Vec<u8>,
-        public_inputs: Vec<[u8; 32]>,
-    ) -> Result<()> {
-        require!(!input_nullifiers.is_empty(), ShieldError::InvalidAmount);
-        verify_groth16(&ctx.accounts.consolidate_verifier, &proof, &public_inputs)?;
-
-        let nullifier_count = input_nullifiers.len();
-        for nullifier in input_nullifiers {
-            track_nullifier(&mut ctx.accounts.nullifier_set, nullifier)?;
-            emit!(NullifierConsumed { nullifier });
+    /// Apply a matured delay or fee collector proposal; callable by anyone once its ETA has passed
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>, proposal_id: u64) -> Result<()> {
+        let proposal = ctx
+            .accounts
+            .governance_queue
+            .take_ready(proposal_id, Clock::get()?.unix_timestamp)?;
+        match proposal.action {
+            GovernanceAction::SetDelay { delay_seconds } => {
+                ctx.accounts.governance_queue.delay_seconds = delay_seconds;
+            }
+            GovernanceAction::SetFeeCollector { fee_collector } => {
+                ctx.accounts.global_state.fee_collector = fee_collector;
+            }
+            GovernanceAction::ActivateVerifier { .. }
+            | GovernanceAction::SetFee { .. }
+            | GovernanceAction::SetFeeRule { .. } => return err!(ShieldError::ProposalMismatch),
         }
+        emit!(ProposalExecuted { id: proposal_id });
+        Ok(())
+    }
 
-        let _root = ctx.accounts.merkle_tree.append_leaf(output_commitment)?;
-        msg!("Consolidated {} notes into 1", nullifier_count);
+    /// Apply a matured `SetFee` or `SetFeeRule` proposal to the fee schedule.
+    /// `SetFee` also keeps the deposit rates mirrored in `GlobalState`.
+    pub fn execute_fee_update(ctx: Context<ExecuteFeeUpdate>, proposal_id: u64) -> Result<()> {
+        let proposal = ctx
+            .accounts
+            .governance_queue
+            .take_ready(proposal_id, Clock::get()?.unix_timestamp)?;
+        let schedule = &mut ctx.accounts.fee_schedule;
+        match proposal.action {
+            GovernanceAction::SetFee {
+                shield_fee_bps,
+                priority_fee_bps,
+            } => {
+                let global = &mut ctx.accounts.global_state;
+                global.shield_fee_bps = shield_fee_bps;
+                global.priority_fee_bps = priority_fee_bps.max(shield_fee_bps);
+                schedule.rules[FeeOperation::Deposit as usize].bps = global.shield_fee_bps;
+                schedule.rules[FeeOperation::PriorityDeposit as usize].bps = global.priority_fee_bps;
+            }
+            GovernanceAction::SetFeeRule { operation, mint, rule } => schedule.set_rule(operation, mint, rule)?,
+            _ => return err!(ShieldError::ProposalMismatch),
+        }
+        emit!(ProposalExecuted { id: proposal_id });
+        Ok(())
+    }
 
+    /// Switch a circuit to a finalized verifier version once the proposal has matured
+    pub fn execute_verifier_update(ctx: Context<ExecuteVerifierUpdate>, proposal_id: u64) -> Result<()> {
+        let proposal = ctx
+            .accounts
+            .governance_queue
+            .take_ready(proposal_id, Clock::get()?.unix_timestamp)?;
+        let GovernanceAction::ActivateVerifier {
+            circuit_id,
+            version,
+            key_hash,
+        } = proposal.action
+        else {
+            return err!(ShieldError::ProposalMismatch);
+        };
+        let verifier = ctx.accounts.verifier.load()?;
+        require!(
+            verifier.circuit_id == circuit_id as u8 && verifier.version == version,
+            ShieldError::ProposalMismatch
+        );
+        require!(
+            verifier.is_finalized() && verifier.key_hash == key_hash,
+            ShieldError::ProposalMismatch
+        );
+        ctx.accounts.global_state.verifier_versions[circuit_id as usize] = version;
+        emit!(ProposalExecuted { id: proposal_id });
         Ok(())
     }
 
-    /// Admin function to update shield fee (in basis points)
-    pub fn set_fee(ctx: Context<SetFee>, shield_fee_bps: u16, priority_fee_bps: u16) -> Result<()> {
-        let global = &mut ctx.accounts.global_state;
-        global.shield_fee_bps = shield_fee_bps;
-        global.priority_fee_bps = priority_fee_bps.max(shield_fee_bps);
+    /// Guardian function to drop a pending proposal before it executes
+    pub fn cancel_proposal(ctx: Context<CancelProposal>, proposal_id: u64) -> Result<()> {
+        ctx.accounts.governance_queue.cancel(proposal_id)?;
+        emit!(ProposalCancelled { id: proposal_id });
         Ok(())
     }
 
+    /// Fee admin function to queue a fee collector change. Every vault sweep pays the
+    /// fee collector, so it only changes through the timelock.
     pub fn set_fee_collector(ctx: Context<SetFeeCollector>, new_fee_collector: Pubkey) -> Result<()> {
-        let global = &mut ctx.accounts.global_state;
-        require!(ctx.accounts.admin.key() == global.admin, ShieldError::Unauthorized);
-        global.fee_collector = new_fee_collector;
+        ctx.accounts.governance_queue.queue(
+            GovernanceAction::SetFeeCollector {
+                fee_collector: new_fee_collector,
+            },
+            Clock::get()?.unix_timestamp,
+        )?;
         Ok(())
     }
 
-    /// Admin function to reset the nullifier set (for devnet testing only)
-    /// WARNING: This allows double-spending of previously spent notes!
-    pub fn reset_nullifiers(ctx: Context<ResetNullifiers>) -> Result<()> {
+    /// Fee admin function to create the fee schedule and the SOL fee vault.
+    /// Deposit rates start from `GlobalState`; every other operation starts free.
+    pub fn initialize_fee_schedule(ctx: Context<InitializeFeeSchedule>) -> Result<()> {
         let global = &ctx.accounts.global_state;
-        require!(ctx.accounts.admin.key() == global.admin, ShieldError::Unauthorized);
-        ctx.accounts.nullifier_set.nullifiers = Vec::new();
-        msg!("Nullifier set reset by admin");
+        let schedule = &mut ctx.accounts.fee_schedule;
+        schedule.rules = [FeeRule::default(); FeeOperation::COUNT];
+        schedule.rules[FeeOperation::Deposit as usize].bps = global.shield_fee_bps;
+        schedule.rules[FeeOperation::PriorityDeposit as usize].bps = global.priority_fee_bps;
+        schedule.overrides = Vec::new();
+        schedule.bump = ctx.bumps.fee_schedule;
+
+        // Fees are often below the rent-exempt minimum, so the vault is funded up front
+        let rent_exempt = Rent::get()?.minimum_balance(0);
+        let top_up = rent_exempt.saturating_sub(ctx.accounts.sol_fee_vault.lamports());
+        collect_sol_fee(
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.sol_fee_vault.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &[],
+            top_up,
+        )
+    }
+
+    /// Create the fee vault that collects fees charged in `mint`
+    pub fn init_fee_vault(ctx: Context<InitFeeVault>) -> Result<()> {
+        msg!("Fee vault initialized for mint: {}", ctx.accounts.mint.key());
         Ok(())
     }
 
-    /// Admin function to withdraw NOC from vault (for devnet corrections)
-    pub fn admin_withdraw_vault_noc(
-        ctx: Context<AdminWithdrawVaultNoc>,
-        amount: u64,
+    /// Fee admin function to queue a fee rule change; see `GovernanceAction::SetFeeRule`
+    pub fn propose_fee_rule(
+        ctx: Context<SetFee>,
+        operation: FeeOperation,
+        mint: Option<Pubkey>,
+        rule: Option<FeeRule>,
     ) -> Result<()> {
-        let vault_bump = ctx.bumps.vault_authority;
-        let mint_key = ctx.accounts.mint.key();
-        let seeds = &[VAULT_AUTHORITY_SEED, mint_key.as_ref(), &[vault_bump]];
-        let signer = &[&seeds[..]];
+        match rule {
+            Some(rule) => rule.validate()?,
+            None => require!(mint.is_some(), ShieldError::InvalidFeeRule),
+        }
+        ctx.accounts.governance_queue.queue(
+            GovernanceAction::SetFeeRule { operation, mint, rule },
+            Clock::get()?.unix_timestamp,
+        )?;
+        Ok(())
+    }
 
-        let transfer_ctx = Transfer {
-            from: ctx.accounts.vault_token_account.to_account_info(),
-            to: ctx.accounts.destination.to_account_info(),
-            authority: ctx.accounts.vault_authority.to_account_info(),
-        };
-        token::transfer(
-            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), transfer_ctx, signer),
+    /// Treasury function to pay collected `mint` fees out to the fee collector
+    pub fn withdraw_fees<'info>(ctx: Context<'_, '_, '_, 'info, WithdrawFees<'info>>, amount: u64) -> Result<()> {
+        let seeds = &[FEE_VAULT_AUTHORITY_SEED, &[ctx.bumps.fee_vault_authority]];
+        CheckedTransfer::new(
+            ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.mint,
+            ctx.remaining_accounts,
+        )
+        .send(
+            ctx.accounts.fee_vault.to_account_info(),
+            ctx.accounts.destination.to_account_info(),
+            ctx.accounts.fee_vault_authority.to_account_info(),
+            &[&seeds[..]],
             amount,
         )?;
-        msg!("Admin withdrew {} tokens from vault", amount);
+        msg!("Withdrew {} fee tokens of mint {}", amount, ctx.accounts.mint.key());
         Ok(())
     }
 
-    /// Admin withdraw from legacy vault (vault-authority PDA without mint)
-    pub fn admin_withdraw_legacy_vault(
-        ctx: Context<AdminWithdrawLegacyVault>,
-        amount: u64,
+    /// Treasury function to pay collected SOL fees out to the fee collector.
+    /// The vault keeps its rent-exempt minimum.
+    pub fn withdraw_sol_fees(ctx: Context<WithdrawSolFees>, amount: u64) -> Result<()> {
+        let available = ctx
+            .accounts
+            .sol_fee_vault
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        require!(amount <= available, ShieldError::InvalidAmount);
+        let seeds = &[SOL_FEE_VAULT_SEED, &[ctx.bumps.sol_fee_vault]];
+        anchor_lang::system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.sol_fee_vault.to_account_info(),
+                    to: ctx.accounts.fee_collector.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+        )?;
+        msg!("Withdrew {} lamports of fees", amount);
+        Ok(())
+    }
+
+    /// Treasury function to move shielded swap fees, which stay in the SOL and NOC
+    /// vaults when charged, into their fee vaults
+    pub fn collect_pool_fees<'info>(ctx: Context<'_, '_, '_, 'info, CollectPoolFees<'info>>) -> Result<()> {
+        let sol_fees = ctx.accounts.sol_ledger.take_fees();
+        let noc_fees = ctx.accounts.noc_ledger.take_fees();
+
+        let sol_vault_seeds = &[SOL_VAULT_SEED, &[ctx.bumps.sol_vault]];
+        collect_sol_fee(
+            &ctx.accounts.sol_vault.to_account_info(),
+            &ctx.accounts.sol_fee_vault.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &[&sol_vault_seeds[..]],
+            sol_fees,
+        )?;
+
+        let mint_key = ctx.accounts.noc_mint.key();
+        let vault_seeds = &[VAULT_AUTHORITY_SEED, mint_key.as_ref(), &[ctx.bumps.vault_authority]];
+        let noc_transfer = CheckedTransfer::new(
+            ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.noc_mint,
+            ctx.remaining_accounts,
+        );
+        collect_token_fee(
+            &noc_transfer,
+            &ctx.accounts.vault_token_account.to_account_info(),
+            &ctx.accounts.noc_fee_vault.to_account_info(),
+            &ctx.accounts.vault_authority.to_account_info(),
+            &[&vault_seeds[..]],
+            noc_fees,
+        )?;
+        ctx.accounts.sol_ledger.require_solvent(sol_vault_holdings(&ctx.accounts.sol_vault)?)?;
+        ctx.accounts.vault_token_account.reload()?;
+        ctx.accounts.noc_ledger.require_solvent(ctx.accounts.vault_token_account.amount)?;
+        msg!("Collected pool fees: {} SOL, {} NOC", sol_fees, noc_fees);
+        Ok(())
+    }
+
+    /// Admin function to move legacy nullifiers into per-nullifier PDAs.
+    /// Remaining accounts: the NullifierRecord PDA of each leading legacy entry, in order.
+    pub fn migrate_legacy_nullifiers<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MigrateLegacyNullifiers<'info>>,
     ) -> Result<()> {
-        let vault_bump = ctx.bumps.legacy_vault_authority;
-        // Legacy vault uses just "vault-authority" seed without mint
-        let seeds = &[LEGACY_VAULT_AUTHORITY_SEED, &[vault_bump]];
+        let count = ctx.remaining_accounts.len();
+        require!(
+            count <= ctx.accounts.nullifier_set.nullifiers.len(),
+            ShieldError::InvalidNullifierAccount
+        );
+        let payer = ctx.accounts.admin.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        for (nullifier, record) in ctx.accounts.nullifier_set.nullifiers[..count]
+            .iter()
+            .zip(ctx.remaining_accounts.iter())
+        {
+            migrate_nullifier_record(record, &payer, &system_program, *nullifier)?;
+        }
+        ctx.accounts.nullifier_set.nullifiers.drain(..count);
+        msg!(
+            "Migrated {} legacy nullifiers, {} remaining",
+            count,
+            ctx.accounts.nullifier_set.nullifiers.len()
+        );
+        Ok(())
+    }
+
+    /// Treasury function to move a vault created under the mint-less legacy vault authority
+    /// into the mint's canonical vault. The balance still backs notes, so it is counted
+    /// as deposited; the legacy token account is closed, which makes this one-shot.
+    pub fn migrate_legacy_vault<'info>(ctx: Context<'_, '_, '_, 'info, MigrateLegacyVault<'info>>) -> Result<()> {
+        let amount = ctx.accounts.legacy_vault_token_account.amount;
+        let balance_before = ctx.accounts.vault_token_account.amount;
+        let seeds = &[LEGACY_VAULT_AUTHORITY_SEED, &[ctx.bumps.legacy_vault_authority]];
         let signer = &[&seeds[..]];
 
-        let transfer_ctx = Transfer {
-            from: ctx.accounts.vault_token_account.to_account_info(),
-            to: ctx.accounts.destination.to_account_info(),
-            authority: ctx.accounts.legacy_vault_authority.to_account_info(),
-        };
-        token::transfer(
-            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), transfer_ctx, signer),
+        CheckedTransfer::new(
+            ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.mint,
+            ctx.remaining_accounts,
+        )
+        .send(
+            ctx.accounts.legacy_vault_token_account.to_account_info(),
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.legacy_vault_authority.to_account_info(),
+            signer,
+            amount,
+        )?;
+        token_interface::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.legacy_vault_token_account.to_account_info(),
+                destination: ctx.accounts.admin.to_account_info(),
+                authority: ctx.accounts.legacy_vault_authority.to_account_info(),
+            },
+            signer,
+        ))?;
+
+        // A transfer-fee mint withholds part of the balance on the way
+        ctx.accounts.vault_token_account.reload()?;
+        let received = ctx.accounts.vault_token_account.amount - balance_before;
+        ctx.accounts.ledger.record_deposit(received)?;
+        ctx.accounts.ledger.require_solvent(ctx.accounts.vault_token_account.amount)?;
+        msg!("Migrated legacy vault of mint {}: {} tokens, {} received", ctx.accounts.mint.key(), amount, received);
+        Ok(())
+    }
+
+    /// Treasury function to pay a token vault's surplus over its `VaultLedger` liabilities
+    /// (accrued fees and anything donated) to the fee collector. Note funds can't move.
+    pub fn sweep_vault_surplus<'info>(ctx: Context<'_, '_, '_, 'info, SweepVaultSurplus<'info>>) -> Result<()> {
+        let ledger = &mut ctx.accounts.ledger;
+        let amount = ledger.surplus(ctx.accounts.vault_token_account.amount);
+        let fees = ledger.take_fees();
+
+        let mint_key = ctx.accounts.mint.key();
+        let seeds = &[VAULT_AUTHORITY_SEED, mint_key.as_ref(), &[ctx.bumps.vault_authority]];
+        if amount > 0 {
+            CheckedTransfer::new(
+                ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.mint,
+                ctx.remaining_accounts,
+            )
+            .send(
+                ctx.accounts.vault_token_account.to_account_info(),
+                ctx.accounts.destination.to_account_info(),
+                ctx.accounts.vault_authority.to_account_info(),
+                &[&seeds[..]],
+                amount,
+            )?;
+        }
+        ctx.accounts.vault_token_account.reload()?;
+        ctx.accounts.ledger.require_solvent(ctx.accounts.vault_token_account.amount)?;
+        emit!(VaultSurplusSwept {
+            mint: mint_key,
+            destination: ctx.accounts.destination.key(),
+            amount,
+            fees,
+            liabilities: ctx.accounts.ledger.liabilities(),
+        });
+        Ok(())
+    }
+
+    /// Treasury function to pay the SOL vault's surplus to the fee collector, as
+    /// `sweep_vault_surplus`. The vault keeps its rent-exempt minimum.
+    pub fn sweep_sol_vault_surplus(ctx: Context<SweepSolVaultSurplus>) -> Result<()> {
+        let spendable = sol_vault_holdings(&ctx.accounts.sol_vault)?;
+        let ledger = &mut ctx.accounts.ledger;
+        let amount = ledger.surplus(spendable);
+        let fees = ledger.take_fees();
+
+        let seeds = &[SOL_VAULT_SEED, &[ctx.bumps.sol_vault]];
+        collect_sol_fee(
+            &ctx.accounts.sol_vault.to_account_info(),
+            &ctx.accounts.fee_collector.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &[&seeds[..]],
+            amount,
+        )?;
+        ctx.accounts.ledger.require_solvent(sol_vault_holdings(&ctx.accounts.sol_vault)?)?;
+        emit!(VaultSurplusSwept {
+            mint: NATIVE_MINT,
+            destination: ctx.accounts.fee_collector.key(),
             amount,
+            fees,
+            liabilities: ctx.accounts.ledger.liabilities(),
+        });
+        Ok(())
+    }
+
+    /// Treasury function to let notes hold `mint`. `mint_field` is the note `tokenMint`
+    /// value for it (little-endian, like other field arguments) and must be unused.
+    /// Register native SOL under the wrapped SOL mint.
+    pub fn register_asset(
+        ctx: Context<RegisterAsset>,
+        mint_field: [u8; 32],
+        max_deposit: u64,
+        deposit_cap: u64,
+    ) -> Result<()> {
+        require_supported_mint(&ctx.accounts.mint.to_account_info())?;
+        let mint_field = canonical_field_from_le(&mint_field)?;
+        let asset = &mut ctx.accounts.asset;
+        asset.mint = ctx.accounts.mint.key();
+        asset.mint_field = mint_field;
+        asset.decimals = ctx.accounts.mint.decimals;
+        asset.enabled = true;
+        asset.max_deposit = max_deposit;
+        asset.deposit_cap = deposit_cap;
+        asset.total_deposited = 0;
+        asset.bump = ctx.bumps.asset;
+        ctx.accounts.mint_field_record.mint = asset.mint;
+        emit!(AssetRegistered {
+            mint: asset.mint,
+            mint_field,
+        });
+        Ok(())
+    }
+
+    /// Treasury function to enable or disable an asset and change its deposit limits
+    pub fn set_asset_config(
+        ctx: Context<SetAssetConfig>,
+        enabled: bool,
+        max_deposit: u64,
+        deposit_cap: u64,
+    ) -> Result<()> {
+        let asset = &mut ctx.accounts.asset;
+        asset.enabled = enabled;
+        asset.max_deposit = max_deposit;
+        asset.deposit_cap = deposit_cap;
+        msg!("Asset {} enabled: {}, max deposit: {}, cap: {}", asset.mint, enabled, max_deposit, deposit_cap);
+        Ok(())
+    }
+
+    /// Treasury function to start the `VaultLedger` of a registered token asset. Whatever
+    /// the vault already holds is counted as owed to notes, so none of it can be claimed later.
+    pub fn init_vault_ledger(ctx: Context<InitVaultLedger>) -> Result<()> {
+        let opening_balance = ctx.accounts.vault_token_account.amount;
+        open_ledger(&mut ctx.accounts.ledger, ctx.accounts.asset.mint, opening_balance, ctx.bumps.ledger);
+        Ok(())
+    }
+
+    /// Treasury function to start the native SOL `VaultLedger`, as `init_vault_ledger`.
+    /// The ledger counts the vault above its rent-exempt minimum, so the admin adds that
+    /// reserve on top of what the vault already holds, all of which stays owed to notes.
+    pub fn init_sol_vault_ledger(ctx: Context<InitSolVaultLedger>) -> Result<()> {
+        let opening_balance = ctx.accounts.sol_vault.lamports();
+        transfer_lamports(
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.sol_vault.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &[],
+            Rent::get()?.minimum_balance(0),
         )?;
-        msg!("Admin withdrew {} tokens from legacy vault", amount);
+        open_ledger(&mut ctx.accounts.ledger, NATIVE_MINT, opening_balance, ctx.bumps.ledger);
         Ok(())
     }
 
-    pub fn transparent_deposit(
-        ctx: Context<TransparentDeposit>,
+    /// Remaining accounts: the extra accounts of a transfer-hook mint, if any.
+    pub fn transparent_deposit<'info>(
+        ctx: Context<'_, '_, '_, 'info, TransparentDeposit<'info>>,
         commitment: [u8; 32],
         nullifier: [u8; 32],
         amount: u64,
@@ -272,34 +821,53 @@ pub mod noctura_shield {
         priority_lane: bool,
     ) -> Result<()> {
         require!(amount > 0, ShieldError::InvalidAmount);
-        verify_groth16(&ctx.accounts.verifier, &proof, &public_inputs)?;
+        // The note holds what reaches the vault
+        let credited = net_of_transfer_fee(&ctx.accounts.mint.to_account_info(), amount)?;
+        require!(credited > 0, ShieldError::FeeExceedsAmount);
+        let layout = PublicInputLayout::Deposit {
+            commitment: &commitment,
+            amount: credited,
+            token_mint: &ctx.accounts.asset.mint_field,
+        };
+        verify_groth16(&*ctx.accounts.verifier.load()?, &proof, &public_inputs, &layout)?;
+        ctx.accounts.asset.record_deposit(credited)?;
+        ctx.accounts.ledger.record_deposit(credited)?;
 
-        let fee_bps = if priority_lane {
-            ctx.accounts.global_state.priority_fee_bps
+        let operation = if priority_lane {
+            FeeOperation::PriorityDeposit
         } else {
-            ctx.accounts.global_state.shield_fee_bps
+            FeeOperation::Deposit
         };
-        let fee_amount = amount.saturating_mul(fee_bps as u64) / 10_000;
+        let fee_amount = ctx.accounts.fee_schedule.charge(operation, ctx.accounts.mint.key(), amount);
 
         // Move tokens into the vault.
-        let transfer_to_vault = Transfer {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            to: ctx.accounts.vault_token_account.to_account_info(),
-            authority: ctx.accounts.payer.to_account_info(),
-        };
-        token::transfer(CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_to_vault), amount)?;
+        let deposit_transfer = CheckedTransfer::new(
+            ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.mint,
+            ctx.remaining_accounts,
+        );
+        deposit_transfer.send(
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+            &[],
+            amount,
+        )?;
 
         // Collect protocol fee.
-        if fee_amount > 0 {
-            let transfer_fee = Transfer {
-                from: ctx.accounts.user_token_account.to_account_info(),
-                to: ctx.accounts.fee_collector_token_account.to_account_info(),
-                authority: ctx.accounts.payer.to_account_info(),
-            };
-            token::transfer(CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_fee), fee_amount)?;
-        }
+        collect_token_fee(
+            &deposit_transfer,
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.fee_vault.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &[],
+            fee_amount,
+        )?;
 
-        let new_root = ctx.accounts.merkle_tree.append_leaf(commitment)?;
+        ctx.accounts.vault_token_account.reload()?;
+        ctx.accounts.ledger.require_solvent(ctx.accounts.vault_token_account.amount)?;
+
+        let new_root = load_tree_mut(&ctx.accounts.merkle_tree)?.append_commitment(&commitment)?;
         emit!(CommitmentInserted {
             commitment,
             nullifier,
@@ -319,9 +887,24 @@ pub mod noctura_shield {
         amount: u64,
         proof: Vec<u8>,
         public_inputs: Vec<[u8; 32]>,
+        priority_lane: bool,
     ) -> Result<()> {
         require!(amount > 0, ShieldError::InvalidAmount);
-        verify_groth16(&ctx.accounts.verifier, &proof, &public_inputs)?;
+        let layout = PublicInputLayout::Deposit {
+            commitment: &commitment,
+            amount,
+            token_mint: &ctx.accounts.asset.mint_field,
+        };
+        verify_groth16(&*ctx.accounts.verifier.load()?, &proof, &public_inputs, &layout)?;
+        ctx.accounts.asset.record_deposit(amount)?;
+        ctx.accounts.ledger.record_deposit(amount)?;
+
+        let operation = if priority_lane {
+            FeeOperation::PriorityDeposit
+        } else {
+            FeeOperation::Deposit
+        };
+        let fee_amount = ctx.accounts.fee_schedule.charge(operation, NATIVE_MINT, amount);
 
         // Transfer native SOL from payer to vault
         anchor_lang::solana_program::program::invoke(
@@ -337,91 +920,265 @@ pub mod noctura_shield {
             ],
         )?;
 
+        // Collect protocol fee
+        collect_sol_fee(
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.sol_fee_vault.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &[],
+            fee_amount,
+        )?;
+
+        ctx.accounts.ledger.require_solvent(sol_vault_holdings(&ctx.accounts.sol_vault)?)?;
+
         // Add commitment to Merkle tree
-        let new_root = ctx.accounts.merkle_tree.append_leaf(commitment)?;
+        let new_root = load_tree_mut(&ctx.accounts.merkle_tree)?.append_commitment(&commitment)?;
         emit!(CommitmentInserted {
             commitment,
             nullifier,
             new_root,
-            is_priority: false,
+            is_priority: priority_lane,
         });
 
         Ok(())
     }
 
-    pub fn shielded_transfer(
-        ctx: Context<ShieldedTransfer>,
+    pub fn shielded_transfer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ShieldedTransfer<'info>>,
         input_nullifiers: Vec<[u8; 32]>,
         output_commitments: Vec<[u8; 32]>,
+        merkle_root: [u8; 32],
         proof: Vec<u8>,
         public_inputs: Vec<[u8; 32]>,
     ) -> Result<()> {
         require!(!input_nullifiers.is_empty(), ShieldError::InvalidAmount);
         require!(!output_commitments.is_empty(), ShieldError::InvalidAmount);
-        verify_groth16(&ctx.accounts.transfer_verifier, &proof, &public_inputs)?;
+        load_tree(&ctx.accounts.merkle_tree)?.require_known_root(&field_from_le(&merkle_root))?;
+        let layout = PublicInputLayout::Transfer {
+            merkle_root: &merkle_root,
+            output_commitments: &output_commitments,
+            nullifiers: &input_nullifiers,
+        };
+        verify_groth16(&*ctx.accounts.transfer_verifier.load()?, &proof, &public_inputs, &layout)?;
 
-        for nullifier in input_nullifiers {
-            track_nullifier(&mut ctx.accounts.nullifier_set, nullifier)?;
+        // One NullifierRecord PDA per input nullifier, in order, as remaining accounts
+        require!(
+            ctx.remaining_accounts.len() == input_nullifiers.len(),
+            ShieldError::InvalidNullifierAccount
+        );
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let fee = ctx.accounts.fee_schedule.charge(FeeOperation::Transfer, NATIVE_MINT, 0);
+        collect_sol_fee(&payer, &ctx.accounts.sol_fee_vault.to_account_info(), &system_program, &[], fee)?;
+        for (nullifier, record) in input_nullifiers.into_iter().zip(ctx.remaining_accounts.iter()) {
+            consume_nullifier(&ctx.accounts.nullifier_set, record, &payer, &system_program, nullifier)?;
             emit!(NullifierConsumed { nullifier });
         }
 
         for commitment in output_commitments {
-            let _root = ctx.accounts.merkle_tree.append_leaf(commitment)?;
+            let _root = load_tree_mut(&ctx.accounts.merkle_tree)?.append_commitment(&commitment)?;
+        }
+
+        Ok(())
+    }
+
+    /// Several `shielded_transfer`s under one batched Groth16 check (k + 3 pairings
+    /// instead of 4k), so a relayer can bundle users into one transaction. Remaining
+    /// accounts are every transfer's NullifierRecord PDAs, concatenated in order.
+    pub fn shielded_transfer_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ShieldedTransfer<'info>>,
+        transfers: Vec<TransferArgs>,
+    ) -> Result<()> {
+        require!(!transfers.is_empty(), ShieldError::InvalidAmount);
+        {
+            let tree = load_tree(&ctx.accounts.merkle_tree)?;
+            for transfer in &transfers {
+                require!(!transfer.input_nullifiers.is_empty(), ShieldError::InvalidAmount);
+                require!(!transfer.output_commitments.is_empty(), ShieldError::InvalidAmount);
+                tree.require_known_root(&field_from_le(&transfer.merkle_root))?;
+            }
+        }
+        let batch: Vec<BatchProof> = transfers
+            .iter()
+            .map(|transfer| BatchProof {
+                proof: &transfer.proof,
+                public_inputs: &transfer.public_inputs,
+                layout: PublicInputLayout::Transfer {
+                    merkle_root: &transfer.merkle_root,
+                    output_commitments: &transfer.output_commitments,
+                    nullifiers: &transfer.input_nullifiers,
+                },
+            })
+            .collect();
+        verify_groth16_batch(&*ctx.accounts.transfer_verifier.load()?, &batch)?;
+
+        let nullifier_count: usize = transfers.iter().map(|transfer| transfer.input_nullifiers.len()).sum();
+        require!(
+            ctx.remaining_accounts.len() == nullifier_count,
+            ShieldError::InvalidNullifierAccount
+        );
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let fee: u64 = transfers
+            .iter()
+            .map(|_| ctx.accounts.fee_schedule.charge(FeeOperation::Transfer, NATIVE_MINT, 0))
+            .sum();
+        collect_sol_fee(&payer, &ctx.accounts.sol_fee_vault.to_account_info(), &system_program, &[], fee)?;
+        let mut records = ctx.remaining_accounts.iter();
+        for transfer in transfers {
+            for (nullifier, record) in transfer.input_nullifiers.into_iter().zip(records.by_ref()) {
+                consume_nullifier(&ctx.accounts.nullifier_set, record, &payer, &system_program, nullifier)?;
+                emit!(NullifierConsumed { nullifier });
+            }
+            for commitment in transfer.output_commitments {
+                let _root = load_tree_mut(&ctx.accounts.merkle_tree)?.append_commitment(&commitment)?;
+            }
         }
 
         Ok(())
     }
 
-    pub fn transparent_withdraw(
-        ctx: Context<TransparentWithdraw>,
+    /// `relayer` is paid `relayer_fee` out of the note, both bound by the proof;
+    /// pass the default pubkey and 0 when submitting without a relayer.
+    /// Remaining accounts: the extra accounts of a transfer-hook mint, if any.
+    #[allow(clippy::too_many_arguments)]
+    pub fn transparent_withdraw<'info>(
+        ctx: Context<'_, '_, '_, 'info, TransparentWithdraw<'info>>,
         amount: u64,
+        merkle_root: [u8; 32],
         proof: Vec<u8>,
         public_inputs: Vec<[u8; 32]>,
         nullifier: [u8; 32],
+        relayer: Pubkey,
+        relayer_fee: u64,
     ) -> Result<()> {
         require!(amount > 0, ShieldError::InvalidAmount);
-        verify_groth16(&ctx.accounts.withdraw_verifier, &proof, &public_inputs)?;
-        track_nullifier(&mut ctx.accounts.nullifier_set, nullifier)?;
+        load_tree(&ctx.accounts.merkle_tree)?.require_known_root(&field_from_le(&merkle_root))?;
+        let layout = PublicInputLayout::Withdraw {
+            merkle_root: &merkle_root,
+            receiver: &ctx.accounts.receiver_token_account.owner,
+            amount,
+            nullifier: &nullifier,
+            relayer: &relayer,
+            relayer_fee,
+            token_mint: &ctx.accounts.asset.mint_field,
+        };
+        verify_groth16(&*ctx.accounts.withdraw_verifier.load()?, &proof, &public_inputs, &layout)?;
+        ctx.accounts.asset.record_withdrawal(amount);
+        ctx.accounts.ledger.record_withdrawal(amount)?;
+        consume_nullifier(
+            &ctx.accounts.nullifier_set,
+            &ctx.accounts.nullifier_record.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            nullifier,
+        )?;
 
         let vault_bump = ctx.bumps.vault_authority;
         let mint_key = ctx.accounts.mint.key();
         let seeds = &[VAULT_AUTHORITY_SEED, mint_key.as_ref(), &[vault_bump]];
         let signer = &[&seeds[..]];
 
-        let transfer_ctx = Transfer {
-            from: ctx.accounts.vault_token_account.to_account_info(),
-            to: ctx.accounts.receiver_token_account.to_account_info(),
-            authority: ctx.accounts.vault_authority.to_account_info(),
-        };
-        token::transfer(
-            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), transfer_ctx, signer),
-            amount,
+        // The protocol and relayer fees come out of the withdrawn note
+        let fee = ctx.accounts.fee_schedule.charge(FeeOperation::Withdraw, mint_key, amount);
+        let payout = recipient_share(amount, fee, relayer_fee)?;
+
+        let vault_transfer = CheckedTransfer::new(
+            ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.mint,
+            ctx.remaining_accounts,
+        );
+        vault_transfer.send(
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.receiver_token_account.to_account_info(),
+            ctx.accounts.vault_authority.to_account_info(),
+            signer,
+            payout,
+        )?;
+        if relayer_fee > 0 {
+            let relayer_token_account = ctx
+                .accounts
+                .relayer_token_account
+                .as_ref()
+                .ok_or(error!(ShieldError::InvalidRelayer))?;
+            require_keys_eq!(relayer_token_account.owner, relayer, ShieldError::InvalidRelayer);
+            vault_transfer.send(
+                ctx.accounts.vault_token_account.to_account_info(),
+                relayer_token_account.to_account_info(),
+                ctx.accounts.vault_authority.to_account_info(),
+                signer,
+                relayer_fee,
+            )?;
+            emit!(RelayerPaid {
+                relayer,
+                mint: mint_key,
+                fee: relayer_fee,
+            });
+        }
+        collect_token_fee(
+            &vault_transfer,
+            &ctx.accounts.vault_token_account.to_account_info(),
+            &ctx.accounts.fee_vault.to_account_info(),
+            &ctx.accounts.vault_authority.to_account_info(),
+            signer,
+            fee,
         )?;
+        ctx.accounts.vault_token_account.reload()?;
+        ctx.accounts.ledger.require_solvent(ctx.accounts.vault_token_account.amount)?;
         emit!(NullifierConsumed { nullifier });
         Ok(())
     }
 
-    /// Transparent withdraw for native SOL: withdraw from vault PDA to recipient
+    /// Transparent withdraw for native SOL: withdraw from vault PDA to recipient,
+    /// paying `relayer_fee` to `relayer` as in `transparent_withdraw`
+    #[allow(clippy::too_many_arguments)]
     pub fn transparent_withdraw_sol(
         ctx: Context<TransparentWithdrawSol>,
         amount: u64,
+        merkle_root: [u8; 32],
         proof: Vec<u8>,
         public_inputs: Vec<[u8; 32]>,
         nullifier: [u8; 32],
+        relayer: Pubkey,
+        relayer_fee: u64,
     ) -> Result<()> {
-        verify_groth16(&ctx.accounts.withdraw_verifier, &proof, &public_inputs)?;
-        track_nullifier(&mut ctx.accounts.nullifier_set, nullifier)?;
+        require!(amount > 0, ShieldError::InvalidAmount);
+        let recipient = ctx.accounts.recipient.key();
+        load_tree(&ctx.accounts.merkle_tree)?.require_known_root(&field_from_le(&merkle_root))?;
+        let layout = PublicInputLayout::Withdraw {
+            merkle_root: &merkle_root,
+            receiver: &recipient,
+            amount,
+            nullifier: &nullifier,
+            relayer: &relayer,
+            relayer_fee,
+            token_mint: &ctx.accounts.asset.mint_field,
+        };
+        verify_groth16(&*ctx.accounts.withdraw_verifier.load()?, &proof, &public_inputs, &layout)?;
+        ctx.accounts.asset.record_withdrawal(amount);
+        ctx.accounts.ledger.record_withdrawal(amount)?;
+        consume_nullifier(
+            &ctx.accounts.nullifier_set,
+            &ctx.accounts.nullifier_record.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            nullifier,
+        )?;
 
         let sol_vault_bump = ctx.bumps.sol_vault;
         let seeds = &[SOL_VAULT_SEED, &[sol_vault_bump]];
         let signer = &[&seeds[..]];
 
+        // The protocol and relayer fees come out of the withdrawn note
+        let fee = ctx.accounts.fee_schedule.charge(FeeOperation::Withdraw, NATIVE_MINT, amount);
+        let payout = recipient_share(amount, fee, relayer_fee)?;
+
         // Transfer native SOL from vault to recipient using invoke_signed
         anchor_lang::solana_program::program::invoke_signed(
             &anchor_lang::solana_program::system_instruction::transfer(
                 &ctx.accounts.sol_vault.key(),
                 &ctx.accounts.recipient.key(),
-                amount,
+                payout,
             ),
             &[
                 ctx.accounts.sol_vault.to_account_info(),
@@ -430,76 +1187,140 @@ pub mod noctura_shield {
             ],
             signer,
         )?;
+        collect_sol_fee(
+            &ctx.accounts.sol_vault.to_account_info(),
+            &ctx.accounts.sol_fee_vault.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            signer,
+            fee,
+        )?;
+        if relayer_fee > 0 {
+            let relayer_account = ctx
+                .accounts
+                .relayer
+                .as_ref()
+                .ok_or(error!(ShieldError::InvalidRelayer))?;
+            require_keys_eq!(relayer_account.key(), relayer, ShieldError::InvalidRelayer);
+            anchor_lang::solana_program::program::invoke_signed(
+                &anchor_lang::solana_program::system_instruction::transfer(
+                    &ctx.accounts.sol_vault.key(),
+                    &relayer,
+                    relayer_fee,
+                ),
+                &[
+                    ctx.accounts.sol_vault.to_account_info(),
+                    relayer_account.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                signer,
+            )?;
+            emit!(RelayerPaid {
+                relayer,
+                mint: NATIVE_MINT,
+                fee: relayer_fee,
+            });
+        }
+        ctx.accounts.ledger.require_solvent(sol_vault_holdings(&ctx.accounts.sol_vault)?)?;
         emit!(NullifierConsumed { nullifier });
         Ok(())
     }
 
-    /// Partial withdraw: withdraw some amount to transparent wallet, keep change shielded
-    pub fn partial_withdraw(
-        ctx: Context<PartialWithdraw>,
+    /// Partial withdraw: withdraw some amount to transparent wallet, keep change shielded.
+    /// `relayer_fee` comes out of `withdraw_amount`, and remaining accounts are used, as in `transparent_withdraw`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn partial_withdraw<'info>(
+        ctx: Context<'_, '_, '_, 'info, PartialWithdraw<'info>>,
         withdraw_amount: u64,
         change_commitment: [u8; 32],
+        merkle_root: [u8; 32],
         proof: Vec<u8>,
         public_inputs: Vec<[u8; 32]>,
         nullifier: [u8; 32],
+        relayer: Pubkey,
+        relayer_fee: u64,
     ) -> Result<()> {
         require!(withdraw_amount > 0, ShieldError::InvalidAmount);
-        verify_groth16(&ctx.accounts.partial_withdraw_verifier, &proof, &public_inputs)?;
-        track_nullifier(&mut ctx.accounts.nullifier_set, nullifier)?;
+        load_tree(&ctx.accounts.merkle_tree)?.require_known_root(&field_from_le(&merkle_root))?;
+        let layout = PublicInputLayout::PartialWithdraw {
+            merkle_root: &merkle_root,
+            receiver: &ctx.accounts.receiver_token_account.owner,
+            withdraw_amount,
+            change_commitment: &change_commitment,
+            nullifier: &nullifier,
+            relayer: &relayer,
+            relayer_fee,
+            token_mint: &ctx.accounts.asset.mint_field,
+        };
+        verify_groth16(&*ctx.accounts.partial_withdraw_verifier.load()?, &proof, &public_inputs, &layout)?;
+        ctx.accounts.asset.record_withdrawal(withdraw_amount);
+        ctx.accounts.ledger.record_withdrawal(withdraw_amount)?;
+        consume_nullifier(
+            &ctx.accounts.nullifier_set,
+            &ctx.accounts.nullifier_record.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            nullifier,
+        )?;
 
-        // Transfer withdraw_amount to recipient
+        // Transfer withdraw_amount, less the protocol fee, to recipient
         let vault_bump = ctx.bumps.vault_authority;
         let mint_key = ctx.accounts.mint.key();
         let seeds = &[VAULT_AUTHORITY_SEED, mint_key.as_ref(), &[vault_bump]];
         let signer = &[&seeds[..]];
 
-        let transfer_ctx = Transfer {
-            from: ctx.accounts.vault_token_account.to_account_info(),
-            to: ctx.accounts.receiver_token_account.to_account_info(),
-            authority: ctx.accounts.vault_authority.to_account_info(),
-        };
-        token::transfer(
-            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), transfer_ctx, signer),
-            withdraw_amount,
+        let fee = ctx.accounts.fee_schedule.charge(FeeOperation::Withdraw, mint_key, withdraw_amount);
+        let payout = recipient_share(withdraw_amount, fee, relayer_fee)?;
+
+        let vault_transfer = CheckedTransfer::new(
+            ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.mint,
+            ctx.remaining_accounts,
+        );
+        vault_transfer.send(
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.receiver_token_account.to_account_info(),
+            ctx.accounts.vault_authority.to_account_info(),
+            signer,
+            payout,
+        )?;
+        if relayer_fee > 0 {
+            let relayer_token_account = ctx
+                .accounts
+                .relayer_token_account
+                .as_ref()
+                .ok_or(error!(ShieldError::InvalidRelayer))?;
+            require_keys_eq!(relayer_token_account.owner, relayer, ShieldError::InvalidRelayer);
+            vault_transfer.send(
+                ctx.accounts.vault_token_account.to_account_info(),
+                relayer_token_account.to_account_info(),
+                ctx.accounts.vault_authority.to_account_info(),
+                signer,
+                relayer_fee,
+            )?;
+            emit!(RelayerPaid {
+                relayer,
+                mint: mint_key,
+                fee: relayer_fee,
+            });
+        }
+        collect_token_fee(
+            &vault_transfer,
+            &ctx.accounts.vault_token_account.to_account_info(),
+            &ctx.accounts.fee_vault.to_account_info(),
+            &ctx.accounts.vault_authority.to_account_info(),
+            signer,
+            fee,
         )?;
+        ctx.accounts.vault_token_account.reload()?;
+        ctx.accounts.ledger.require_solvent(ctx.accounts.vault_token_account.amount)?;
 
         // Add change commitment to merkle tree
-        let _new_root = ctx.accounts.merkle_tree.append_leaf(change_commitment)?;
+        let _new_root = load_tree_mut(&ctx.accounts.merkle_tree)?.append_commitment(&change_commitment)?;
 
         emit!(NullifierConsumed { nullifier });
         Ok(())
     }
 
-    /// Debug instruction to test alt_bn128 syscall directly
-    pub fn test_scalar_mul(_ctx: Context<TestScalarMul>, input: Vec<u8>) -> Result<()> {
-        use solana_program::alt_bn128::prelude::alt_bn128_multiplication;
-        use solana_program::log::sol_log_data;
-
-        sol_log_data(&[b"test.input.len", &(input.len() as u64).to_le_bytes()]);
-        sol_log_data(&[b"test.input", &input]);
-
-        match alt_bn128_multiplication(&input) {
-            Ok(result) => {
-                sol_log_data(&[b"test.success", &result]);
-                Ok(())
-            }
-            Err(e) => {
-                let err_code: u64 = e.into();
-                sol_log_data(&[b"test.error", &err_code.to_le_bytes()]);
-                Err(error!(ShieldError::InvalidProof))
-            }
-        }
-    }
-
-    /// DEVNET ONLY: Emergency reset nullifiers without admin check
-    /// This allows resetting the nullifier set when admin keypair is lost
-    pub fn emergency_reset_nullifiers(ctx: Context<EmergencyResetNullifiers>) -> Result<()> {
-        msg!("EMERGENCY: Resetting nullifier set (devnet only)");
-        ctx.accounts.nullifier_set.nullifiers.clear();
-        msg!("Nullifier set cleared, new count: {}", ctx.accounts.nullifier_set.nullifiers.len());
-        Ok(())
-    }
-
     /// Admin function to initialize a vault token account for a specific mint
     /// This creates the vault PDA without requiring a deposit
     pub fn init_token_vault(ctx: Context<InitTokenVault>) -> Result<()> {
@@ -533,10 +1354,35 @@ pub mod noctura_shield {
         Ok(())
     }
 
-    /// Add initial liquidity to the shielded pool (admin only, from vault reserves)
-    /// This seeds the pool using existing vault deposits
-    pub fn seed_shielded_pool(
-        ctx: Context<SeedShieldedPool>,
+    /// Create the pool's SOL and token vaults, which hold its reserves apart from note
+    /// deposits. The token vault fixes the pool's NOC mint, which may not charge a
+    /// transfer fee: swap output notes are backed by exactly what the pool sends.
+    pub fn init_pool_vaults(ctx: Context<InitPoolVaults>) -> Result<()> {
+        require_supported_mint(&ctx.accounts.noc_mint.to_account_info())?;
+        require_no_transfer_fee(&ctx.accounts.noc_mint.to_account_info())?;
+
+        // Reserves are counted above the rent-exempt minimum, so the SOL vault is funded up front
+        let rent_exempt = Rent::get()?.minimum_balance(0);
+        let top_up = rent_exempt.saturating_sub(ctx.accounts.pool_sol_vault.lamports());
+        if top_up > 0 {
+            transfer_lamports(
+                &ctx.accounts.admin.to_account_info(),
+                &ctx.accounts.pool_sol_vault.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &[],
+                top_up,
+            )?;
+        }
+
+        msg!("Pool vaults initialized for mint: {}", ctx.accounts.noc_mint.key());
+        Ok(())
+    }
+
+    /// Add initial liquidity to the shielded pool (admin only)
+    /// The SOL and NOC move from the admin into the pool vaults.
+    /// Remaining accounts: the extra accounts of a transfer-hook mint, if any.
+    pub fn seed_shielded_pool<'info>(
+        ctx: Context<'_, '_, '_, 'info, SeedShieldedPool<'info>>,
         sol_amount: u64,
         noc_amount: u64,
     ) -> Result<()> {
@@ -546,21 +1392,47 @@ pub mod noctura_shield {
         require!(pool.enabled, ShieldError::InvalidAmount);
         require!(pool.sol_reserve == 0 && pool.noc_reserve == 0, ShieldError::InvalidAmount);
 
-        // Update pool reserves (tokens are already in the vaults)
+        transfer_lamports(
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.pool_sol_vault.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &[],
+            sol_amount,
+        )?;
+        let noc_credit = net_of_transfer_fee(&ctx.accounts.noc_mint.to_account_info(), noc_amount)?;
+        CheckedTransfer::new(
+            ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.noc_mint,
+            ctx.remaining_accounts,
+        )
+        .send(
+            ctx.accounts.admin_noc_account.to_account_info(),
+            ctx.accounts.pool_token_vault.to_account_info(),
+            ctx.accounts.admin.to_account_info(),
+            &[],
+            noc_amount,
+        )?;
+
         pool.sol_reserve = sol_amount;
-        pool.noc_reserve = noc_amount;
+        pool.noc_reserve = noc_credit;
         // LP tokens = sqrt(sol * noc)
-        pool.lp_total_supply = isqrt((sol_amount as u128) * (noc_amount as u128)) as u64;
+        pool.lp_total_supply = isqrt((sol_amount as u128) * (noc_credit as u128)) as u64;
+        ctx.accounts.pool_token_vault.reload()?;
+        pool.require_backed(
+            pool_sol_balance(&ctx.accounts.pool_sol_vault)?,
+            ctx.accounts.pool_token_vault.amount,
+        )?;
 
         msg!("Shielded pool seeded: {} SOL, {} NOC, {} LP tokens", 
-            sol_amount, noc_amount, pool.lp_total_supply);
+            sol_amount, noc_credit, pool.lp_total_supply);
         Ok(())
     }
 
     /// Add more liquidity to the shielded pool (admin only)
-    /// Simply increases the reserves without requiring the initial zero check
-    pub fn add_pool_liquidity(
-        ctx: Context<AddPoolLiquidity>,
+    /// Moves the amounts into the pool vaults without requiring the initial zero check.
+    /// Remaining accounts: the extra accounts of a transfer-hook mint, if any.
+    pub fn add_pool_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, AddPoolLiquidity<'info>>,
         sol_amount: u64,
         noc_amount: u64,
     ) -> Result<()> {
@@ -568,86 +1440,227 @@ pub mod noctura_shield {
         require!(pool.enabled, ShieldError::InvalidAmount);
 
         if sol_amount > 0 {
+            transfer_lamports(
+                &ctx.accounts.admin.to_account_info(),
+                &ctx.accounts.pool_sol_vault.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &[],
+                sol_amount,
+            )?;
             pool.sol_reserve = pool.sol_reserve.checked_add(sol_amount)
                 .ok_or(error!(ShieldError::CapacityExceeded))?;
         }
         if noc_amount > 0 {
-            pool.noc_reserve = pool.noc_reserve.checked_add(noc_amount)
+            let noc_credit = net_of_transfer_fee(&ctx.accounts.noc_mint.to_account_info(), noc_amount)?;
+            CheckedTransfer::new(
+                ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.noc_mint,
+                ctx.remaining_accounts,
+            )
+            .send(
+                ctx.accounts.admin_noc_account.to_account_info(),
+                ctx.accounts.pool_token_vault.to_account_info(),
+                ctx.accounts.admin.to_account_info(),
+                &[],
+                noc_amount,
+            )?;
+            pool.noc_reserve = pool.noc_reserve.checked_add(noc_credit)
                 .ok_or(error!(ShieldError::CapacityExceeded))?;
         }
 
         // Recalculate LP supply based on new reserves
         let new_lp = isqrt((pool.sol_reserve as u128) * (pool.noc_reserve as u128)) as u64;
         pool.lp_total_supply = new_lp;
+        ctx.accounts.pool_token_vault.reload()?;
+        pool.require_backed(
+            pool_sol_balance(&ctx.accounts.pool_sol_vault)?,
+            ctx.accounts.pool_token_vault.amount,
+        )?;
 
         msg!("Added liquidity: {} SOL, {} NOC. New reserves: {} SOL, {} NOC", 
             sol_amount, noc_amount, pool.sol_reserve, pool.noc_reserve);
         Ok(())
     }
 
-    /// Admin function to directly set pool reserves (for corrections)
-    pub fn set_pool_reserves(
-        ctx: Context<SetPoolReserves>,
-        sol_reserve: u64,
-        noc_reserve: u64,
+    /// Permissionless: derive the pool reserves from the pool vault balances, so they
+    /// can never drift from what the vaults hold. With `skim`, balance beyond the reserves
+    /// (donations) goes to the fee collector instead of joining them.
+    /// Remaining accounts: the extra accounts of a transfer-hook mint, if any.
+    pub fn sync_pool_reserves<'info>(
+        ctx: Context<'_, '_, '_, 'info, SyncPoolReserves<'info>>,
+        skim: bool,
     ) -> Result<()> {
-        let pool = &mut ctx.accounts.shielded_pool;
-        
-        pool.sol_reserve = sol_reserve;
-        pool.noc_reserve = noc_reserve;
-        pool.lp_total_supply = isqrt((sol_reserve as u128) * (noc_reserve as u128)) as u64;
-        pool.enabled = true; // Always enable when reserves are set
-
-        msg!("Pool reserves set: {} SOL, {} NOC (enabled)", sol_reserve, noc_reserve);
-        Ok(())
-    }
+        let (skimmed_sol, skimmed_noc) = ctx.accounts.shielded_pool.sync(
+            pool_sol_balance(&ctx.accounts.pool_sol_vault)?,
+            ctx.accounts.pool_token_vault.amount,
+            skim,
+        );
+
+        if skimmed_sol > 0 {
+            let seeds = &[POOL_SOL_VAULT_SEED, &[ctx.bumps.pool_sol_vault]];
+            transfer_lamports(
+                &ctx.accounts.pool_sol_vault.to_account_info(),
+                &ctx.accounts.fee_collector.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &[&seeds[..]],
+                skimmed_sol,
+            )?;
+        }
+        if skimmed_noc > 0 {
+            let seeds = &[SHIELDED_POOL_SEED, &[ctx.accounts.shielded_pool.bump]];
+            CheckedTransfer::new(
+                ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.noc_mint,
+                ctx.remaining_accounts,
+            )
+            .send(
+                ctx.accounts.pool_token_vault.to_account_info(),
+                ctx.accounts.treasury_noc_account.to_account_info(),
+                ctx.accounts.shielded_pool.to_account_info(),
+                &[&seeds[..]],
+                skimmed_noc,
+            )?;
+        }
+        ctx.accounts.pool_token_vault.reload()?;
+        let pool = &ctx.accounts.shielded_pool;
+        pool.require_backed(
+            pool_sol_balance(&ctx.accounts.pool_sol_vault)?,
+            ctx.accounts.pool_token_vault.amount,
+        )?;
+
+        emit!(PoolReservesSynced {
+            sol_reserve: pool.sol_reserve,
+            noc_reserve: pool.noc_reserve,
+            skimmed_sol,
+            skimmed_noc,
+        });
+        msg!("Pool reserves synced: {} SOL, {} NOC", pool.sol_reserve, pool.noc_reserve);
+        Ok(())
+    }
 
     /// Execute a shielded swap within the pool
     /// User provides ZK proof of valid input note, receives output note
     /// No tokens leave the shielded system!
-    pub fn shielded_pool_swap(
-        ctx: Context<ShieldedPoolSwap>,
+    /// `min_output_amount` is the proof's expectedOutAmount: the exact value of the output note.
+    #[allow(clippy::too_many_arguments)]
+    pub fn shielded_pool_swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, ShieldedPoolSwap<'info>>,
         input_amount: u64,
         min_output_amount: u64,
         input_is_sol: bool, // true = SOL->NOC, false = NOC->SOL
         input_nullifier: [u8; 32],
         output_commitment: [u8; 32],
+        merkle_root: [u8; 32],
         proof: Vec<u8>,
         public_inputs: Vec<[u8; 32]>,
     ) -> Result<()> {
         require!(input_amount > 0, ShieldError::InvalidAmount);
 
         // Verify ZK proof
-        verify_groth16(&ctx.accounts.swap_verifier, &proof, &public_inputs)?;
+        load_tree(&ctx.accounts.merkle_tree)?.require_known_root(&field_from_le(&merkle_root))?;
+        let (input_asset, output_asset) = swap_assets(&ctx.accounts.sol_asset, &ctx.accounts.noc_asset, input_is_sol)?;
+        let layout = PublicInputLayout::Swap {
+            merkle_root: &merkle_root,
+            output_commitment: &output_commitment,
+            input_amount,
+            input_mint: &input_asset.mint_field,
+            output_mint: &output_asset.mint_field,
+            nullifier: &input_nullifier,
+            output_amount: min_output_amount,
+        };
+        verify_groth16(&*ctx.accounts.swap_verifier.load()?, &proof, &public_inputs, &layout)?;
 
         // Track nullifier (prevents double-spend)
-        track_nullifier(&mut ctx.accounts.nullifier_set, input_nullifier)?;
+        consume_nullifier(
+            &ctx.accounts.nullifier_set,
+            &ctx.accounts.nullifier_record.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            input_nullifier,
+        )?;
 
         let pool = &mut ctx.accounts.shielded_pool;
         require!(pool.enabled, ShieldError::InvalidAmount);
 
+        // The protocol fee stays in the input vault, outside the pool reserves
+        let (input_ledger, output_ledger) = if input_is_sol {
+            (&mut ctx.accounts.sol_ledger, &mut ctx.accounts.noc_ledger)
+        } else {
+            (&mut ctx.accounts.noc_ledger, &mut ctx.accounts.sol_ledger)
+        };
+        let fee = charge_pool_swap_fee(&ctx.accounts.fee_schedule, input_ledger.mint, input_amount)?;
+        let pool_input = input_amount - fee;
+        let pool_credit = if input_is_sol {
+            pool_input
+        } else {
+            net_of_transfer_fee(&ctx.accounts.noc_mint.to_account_info(), pool_input)?
+        };
+
         // Calculate output using AMM formula
-        let output_amount = pool.calculate_output(input_amount, input_is_sol)
+        let quoted_amount = pool.calculate_output(pool_credit, input_is_sol)
             .ok_or(error!(ShieldError::InvalidAmount))?;
 
-        // Slippage check
-        require!(output_amount >= min_output_amount, ShieldError::InvalidAmount);
+        // Slippage check; the pool settles the proven note value, never the quote
+        require!(quoted_amount >= min_output_amount, ShieldError::InvalidAmount);
+        let output_amount = min_output_amount;
+        record_shielded_swap(input_ledger, output_ledger, input_amount, fee, output_amount)?;
+
+        pool.settle_swap(input_is_sol, pool_credit, output_amount)?;
 
-        // Update pool reserves
+        // Settle against the pool vaults: the input leaves its note vault for the pool and
+        // the output leaves the pool for its note vault
+        let noc_transfer = CheckedTransfer::new(
+            ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.noc_mint,
+            ctx.remaining_accounts,
+        );
         if input_is_sol {
-            pool.sol_reserve = pool.sol_reserve.checked_add(input_amount)
-                .ok_or(error!(ShieldError::CapacityExceeded))?;
-            pool.noc_reserve = pool.noc_reserve.checked_sub(output_amount)
-                .ok_or(error!(ShieldError::InvalidAmount))?;
+            let seeds = &[SOL_VAULT_SEED, &[ctx.bumps.sol_vault]];
+            transfer_lamports(
+                &ctx.accounts.sol_vault.to_account_info(),
+                &ctx.accounts.pool_sol_vault.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &[&seeds[..]],
+                pool_input,
+            )?;
+            let pool_seeds = &[SHIELDED_POOL_SEED, &[ctx.accounts.shielded_pool.bump]];
+            noc_transfer.send(
+                ctx.accounts.pool_token_vault.to_account_info(),
+                ctx.accounts.vault_token_account.to_account_info(),
+                ctx.accounts.shielded_pool.to_account_info(),
+                &[&pool_seeds[..]],
+                output_amount,
+            )?;
         } else {
-            pool.noc_reserve = pool.noc_reserve.checked_add(input_amount)
-                .ok_or(error!(ShieldError::CapacityExceeded))?;
-            pool.sol_reserve = pool.sol_reserve.checked_sub(output_amount)
-                .ok_or(error!(ShieldError::InvalidAmount))?;
+            let mint_key = ctx.accounts.noc_mint.key();
+            let seeds = &[VAULT_AUTHORITY_SEED, mint_key.as_ref(), &[ctx.bumps.vault_authority]];
+            noc_transfer.send(
+                ctx.accounts.vault_token_account.to_account_info(),
+                ctx.accounts.pool_token_vault.to_account_info(),
+                ctx.accounts.vault_authority.to_account_info(),
+                &[&seeds[..]],
+                pool_input,
+            )?;
+            let pool_seeds = &[POOL_SOL_VAULT_SEED, &[ctx.bumps.pool_sol_vault]];
+            transfer_lamports(
+                &ctx.accounts.pool_sol_vault.to_account_info(),
+                &ctx.accounts.sol_vault.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &[&pool_seeds[..]],
+                output_amount,
+            )?;
         }
+        ctx.accounts.sol_ledger.require_solvent(sol_vault_holdings(&ctx.accounts.sol_vault)?)?;
+        ctx.accounts.vault_token_account.reload()?;
+        ctx.accounts.noc_ledger.require_solvent(ctx.accounts.vault_token_account.amount)?;
+        ctx.accounts.pool_token_vault.reload()?;
+        ctx.accounts.shielded_pool.require_backed(
+            pool_sol_balance(&ctx.accounts.pool_sol_vault)?,
+            ctx.accounts.pool_token_vault.amount,
+        )?;
 
         // Add output commitment to Merkle tree
-        let _new_root = ctx.accounts.merkle_tree.append_leaf(output_commitment)?;
+        let _new_root = load_tree_mut(&ctx.accounts.merkle_tree)?.append_commitment(&output_commitment)?;
 
         emit!(ShieldedSwapExecuted {
             input_nullifier,
@@ -672,53 +1685,131 @@ pub mod noctura_shield {
     /// Execute a shielded swap V2 - supports partial swaps with change
     /// User swaps some amount, receives output token + change in same token
     /// No tokens leave the shielded system!
-    pub fn shielded_pool_swap_v2(
-        ctx: Context<ShieldedPoolSwapV2>,
+    /// `min_output_amount` is the proof's expectedOutAmount: the exact value of the output note.
+    #[allow(clippy::too_many_arguments)]
+    pub fn shielded_pool_swap_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, ShieldedPoolSwapV2<'info>>,
         swap_amount: u64,          // Amount being swapped (not full note amount)
         min_output_amount: u64,
         input_is_sol: bool,        // true = SOL->NOC, false = NOC->SOL
         input_nullifier: [u8; 32],
         output_commitment: [u8; 32],  // Swapped token commitment
         change_commitment: [u8; 32],  // Change commitment (same token as input)
+        merkle_root: [u8; 32],        // Root the input note was proven against
         proof: Vec<u8>,
         public_inputs: Vec<[u8; 32]>,
     ) -> Result<()> {
         require!(swap_amount > 0, ShieldError::InvalidAmount);
 
         // Verify ZK proof (swap_v2 circuit)
-        verify_groth16(&ctx.accounts.swap_v2_verifier, &proof, &public_inputs)?;
+        load_tree(&ctx.accounts.merkle_tree)?.require_known_root(&field_from_le(&merkle_root))?;
+        let (input_asset, output_asset) = swap_assets(&ctx.accounts.sol_asset, &ctx.accounts.noc_asset, input_is_sol)?;
+        let layout = PublicInputLayout::SwapV2 {
+            merkle_root: &merkle_root,
+            output_commitment: &output_commitment,
+            change_commitment: &change_commitment,
+            swap_amount,
+            input_mint: &input_asset.mint_field,
+            output_mint: &output_asset.mint_field,
+            output_amount: min_output_amount,
+            nullifier: &input_nullifier,
+        };
+        verify_groth16(&*ctx.accounts.swap_v2_verifier.load()?, &proof, &public_inputs, &layout)?;
 
         // Track nullifier (prevents double-spend)
-        track_nullifier(&mut ctx.accounts.nullifier_set, input_nullifier)?;
+        consume_nullifier(
+            &ctx.accounts.nullifier_set,
+            &ctx.accounts.nullifier_record.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            input_nullifier,
+        )?;
 
         let pool = &mut ctx.accounts.shielded_pool;
         require!(pool.enabled, ShieldError::InvalidAmount);
 
+        // The protocol fee stays in the input vault, outside the pool reserves
+        let (input_ledger, output_ledger) = if input_is_sol {
+            (&mut ctx.accounts.sol_ledger, &mut ctx.accounts.noc_ledger)
+        } else {
+            (&mut ctx.accounts.noc_ledger, &mut ctx.accounts.sol_ledger)
+        };
+        let fee = charge_pool_swap_fee(&ctx.accounts.fee_schedule, input_ledger.mint, swap_amount)?;
+        let pool_input = swap_amount - fee;
+        let pool_credit = if input_is_sol {
+            pool_input
+        } else {
+            net_of_transfer_fee(&ctx.accounts.noc_mint.to_account_info(), pool_input)?
+        };
+
         // Calculate output using AMM formula (based on swap_amount, not full note)
-        let output_amount = pool.calculate_output(swap_amount, input_is_sol)
+        let quoted_amount = pool.calculate_output(pool_credit, input_is_sol)
             .ok_or(error!(ShieldError::InvalidAmount))?;
 
-        // Slippage check
-        require!(output_amount >= min_output_amount, ShieldError::InvalidAmount);
+        // Slippage check; the pool settles the proven note value, never the quote
+        require!(quoted_amount >= min_output_amount, ShieldError::InvalidAmount);
+        let output_amount = min_output_amount;
+        record_shielded_swap(input_ledger, output_ledger, swap_amount, fee, output_amount)?;
 
-        // Update pool reserves (only swap_amount affects the pool)
+        pool.settle_swap(input_is_sol, pool_credit, output_amount)?;
+
+        // Settle against the pool vaults: the input leaves its note vault for the pool and
+        // the output leaves the pool for its note vault
+        let noc_transfer = CheckedTransfer::new(
+            ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.noc_mint,
+            ctx.remaining_accounts,
+        );
         if input_is_sol {
-            pool.sol_reserve = pool.sol_reserve.checked_add(swap_amount)
-                .ok_or(error!(ShieldError::CapacityExceeded))?;
-            pool.noc_reserve = pool.noc_reserve.checked_sub(output_amount)
-                .ok_or(error!(ShieldError::InvalidAmount))?;
+            let seeds = &[SOL_VAULT_SEED, &[ctx.bumps.sol_vault]];
+            transfer_lamports(
+                &ctx.accounts.sol_vault.to_account_info(),
+                &ctx.accounts.pool_sol_vault.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &[&seeds[..]],
+                pool_input,
+            )?;
+            let pool_seeds = &[SHIELDED_POOL_SEED, &[ctx.accounts.shielded_pool.bump]];
+            noc_transfer.send(
+                ctx.accounts.pool_token_vault.to_account_info(),
+                ctx.accounts.vault_token_account.to_account_info(),
+                ctx.accounts.shielded_pool.to_account_info(),
+                &[&pool_seeds[..]],
+                output_amount,
+            )?;
         } else {
-            pool.noc_reserve = pool.noc_reserve.checked_add(swap_amount)
-                .ok_or(error!(ShieldError::CapacityExceeded))?;
-            pool.sol_reserve = pool.sol_reserve.checked_sub(output_amount)
-                .ok_or(error!(ShieldError::InvalidAmount))?;
+            let mint_key = ctx.accounts.noc_mint.key();
+            let seeds = &[VAULT_AUTHORITY_SEED, mint_key.as_ref(), &[ctx.bumps.vault_authority]];
+            noc_transfer.send(
+                ctx.accounts.vault_token_account.to_account_info(),
+                ctx.accounts.pool_token_vault.to_account_info(),
+                ctx.accounts.vault_authority.to_account_info(),
+                &[&seeds[..]],
+                pool_input,
+            )?;
+            let pool_seeds = &[POOL_SOL_VAULT_SEED, &[ctx.bumps.pool_sol_vault]];
+            transfer_lamports(
+                &ctx.accounts.pool_sol_vault.to_account_info(),
+                &ctx.accounts.sol_vault.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &[&pool_seeds[..]],
+                output_amount,
+            )?;
         }
+        ctx.accounts.sol_ledger.require_solvent(sol_vault_holdings(&ctx.accounts.sol_vault)?)?;
+        ctx.accounts.vault_token_account.reload()?;
+        ctx.accounts.noc_ledger.require_solvent(ctx.accounts.vault_token_account.amount)?;
+        ctx.accounts.pool_token_vault.reload()?;
+        ctx.accounts.shielded_pool.require_backed(
+            pool_sol_balance(&ctx.accounts.pool_sol_vault)?,
+            ctx.accounts.pool_token_vault.amount,
+        )?;
 
         // Add BOTH output commitments to Merkle tree
         // First: swapped token commitment
-        let _root1 = ctx.accounts.merkle_tree.append_leaf(output_commitment)?;
+        let _root1 = load_tree_mut(&ctx.accounts.merkle_tree)?.append_commitment(&output_commitment)?;
         // Second: change commitment (same token as input)
-        let _root2 = ctx.accounts.merkle_tree.append_leaf(change_commitment)?;
+        let _root2 = load_tree_mut(&ctx.accounts.merkle_tree)?.append_commitment(&change_commitment)?;
 
         emit!(ShieldedSwapV2Executed {
             input_nullifier,
@@ -750,98 +1841,102 @@ pub mod noctura_shield {
     }
 
     /// Execute a transparent swap using the on-chain pool
-    /// Tokens are transferred on-chain, no ZK proofs needed
-    pub fn transparent_pool_swap(
-        ctx: Context<TransparentPoolSwap>,
+    /// Tokens are transferred on-chain against the pool vaults, no ZK proofs needed
+    pub fn transparent_pool_swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, TransparentPoolSwap<'info>>,
         input_amount: u64,
         min_output_amount: u64,
         input_is_sol: bool, // true = SOL->NOC, false = NOC->SOL
     ) -> Result<()> {
         require!(input_amount > 0, ShieldError::InvalidAmount);
 
-        let pool = &mut ctx.accounts.shielded_pool;
+        let pool = &ctx.accounts.shielded_pool;
         require!(pool.enabled, ShieldError::InvalidAmount);
 
+        // The protocol fee comes out of the input, straight into the fee vault
+        let input_mint = if input_is_sol { NATIVE_MINT } else { ctx.accounts.noc_mint.key() };
+        let fee = ctx.accounts.fee_schedule.charge(FeeOperation::Swap, input_mint, input_amount);
+        require!(fee < input_amount, ShieldError::FeeExceedsAmount);
+        let pool_input = input_amount - fee;
+        let pool_credit = if input_is_sol {
+            pool_input
+        } else {
+            net_of_transfer_fee(&ctx.accounts.noc_mint.to_account_info(), pool_input)?
+        };
+        let noc_transfer = CheckedTransfer::new(
+            ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.noc_mint,
+            ctx.remaining_accounts,
+        );
+
         // Calculate output using AMM formula (same as shielded)
-        let output_amount = pool.calculate_output(input_amount, input_is_sol)
+        let output_amount = pool.calculate_output(pool_credit, input_is_sol)
             .ok_or(error!(ShieldError::InvalidAmount))?;
 
         // Slippage check
         require!(output_amount >= min_output_amount, ShieldError::InvalidAmount);
 
         if input_is_sol {
-            // User sends SOL, receives NOC
-            // Transfer SOL from user to sol_vault
-            let cpi_ctx = CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.user.to_account_info(),
-                    to: ctx.accounts.sol_vault.to_account_info(),
-                },
-            );
-            anchor_lang::system_program::transfer(cpi_ctx, input_amount)?;
-
-            // Transfer NOC from vault to user
-            let mint_key = ctx.accounts.noc_mint.key();
-            let vault_bump = ctx.bumps.vault_authority;
-            let seeds = &[VAULT_AUTHORITY_SEED, mint_key.as_ref(), &[vault_bump]];
-            let signer_seeds = &[&seeds[..]];
-
-            let transfer_ctx = CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                anchor_spl::token::Transfer {
-                    from: ctx.accounts.vault_token_account.to_account_info(),
-                    to: ctx.accounts.user_noc_account.to_account_info(),
-                    authority: ctx.accounts.vault_authority.to_account_info(),
-                },
-                signer_seeds,
-            );
-            anchor_spl::token::transfer(transfer_ctx, output_amount)?;
+            // User sends SOL to the pool SOL vault, receives NOC from the pool token vault
+            transfer_lamports(
+                &ctx.accounts.user.to_account_info(),
+                &ctx.accounts.pool_sol_vault.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &[],
+                pool_input,
+            )?;
+            collect_sol_fee(
+                &ctx.accounts.user.to_account_info(),
+                &ctx.accounts.sol_fee_vault.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &[],
+                fee,
+            )?;
 
-            // Update reserves
-            pool.sol_reserve = pool.sol_reserve.checked_add(input_amount)
-                .ok_or(error!(ShieldError::CapacityExceeded))?;
-            pool.noc_reserve = pool.noc_reserve.checked_sub(output_amount)
-                .ok_or(error!(ShieldError::InvalidAmount))?;
+            let seeds = &[SHIELDED_POOL_SEED, &[pool.bump]];
+            noc_transfer.send(
+                ctx.accounts.pool_token_vault.to_account_info(),
+                ctx.accounts.user_noc_account.to_account_info(),
+                ctx.accounts.shielded_pool.to_account_info(),
+                &[&seeds[..]],
+                output_amount,
+            )?;
         } else {
-            // User sends NOC, receives SOL
-            // Transfer NOC from user to vault
-            let transfer_ctx = CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                anchor_spl::token::Transfer {
-                    from: ctx.accounts.user_noc_account.to_account_info(),
-                    to: ctx.accounts.vault_token_account.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            );
-            anchor_spl::token::transfer(transfer_ctx, input_amount)?;
-
-            // Transfer SOL from sol_vault to user using invoke_signed
-            let sol_vault_bump = ctx.bumps.sol_vault;
-            let seeds = &[SOL_VAULT_SEED, &[sol_vault_bump]];
-            let signer = &[&seeds[..]];
-
-            anchor_lang::solana_program::program::invoke_signed(
-                &anchor_lang::solana_program::system_instruction::transfer(
-                    &ctx.accounts.sol_vault.key(),
-                    &ctx.accounts.user.key(),
-                    output_amount,
-                ),
-                &[
-                    ctx.accounts.sol_vault.to_account_info(),
-                    ctx.accounts.user.to_account_info(),
-                    ctx.accounts.system_program.to_account_info(),
-                ],
-                signer,
+            // User sends NOC to the pool token vault, receives SOL from the pool SOL vault
+            noc_transfer.send(
+                ctx.accounts.user_noc_account.to_account_info(),
+                ctx.accounts.pool_token_vault.to_account_info(),
+                ctx.accounts.user.to_account_info(),
+                &[],
+                pool_input,
+            )?;
+            collect_token_fee(
+                &noc_transfer,
+                &ctx.accounts.user_noc_account.to_account_info(),
+                &ctx.accounts.noc_fee_vault.to_account_info(),
+                &ctx.accounts.user.to_account_info(),
+                &[],
+                fee,
             )?;
 
-            // Update reserves
-            pool.noc_reserve = pool.noc_reserve.checked_add(input_amount)
-                .ok_or(error!(ShieldError::CapacityExceeded))?;
-            pool.sol_reserve = pool.sol_reserve.checked_sub(output_amount)
-                .ok_or(error!(ShieldError::InvalidAmount))?;
+            let seeds = &[POOL_SOL_VAULT_SEED, &[ctx.bumps.pool_sol_vault]];
+            transfer_lamports(
+                &ctx.accounts.pool_sol_vault.to_account_info(),
+                &ctx.accounts.user.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &[&seeds[..]],
+                output_amount,
+            )?;
         }
 
+        // Update reserves
+        ctx.accounts.shielded_pool.settle_swap(input_is_sol, pool_credit, output_amount)?;
+        ctx.accounts.pool_token_vault.reload()?;
+        ctx.accounts.shielded_pool.require_backed(
+            pool_sol_balance(&ctx.accounts.pool_sol_vault)?,
+            ctx.accounts.pool_token_vault.amount,
+        )?;
+
         emit!(TransparentSwapExecuted {
             user: ctx.accounts.user.key(),
             is_sol_to_noc: input_is_sol,
@@ -858,10 +1953,24 @@ pub mod noctura_shield {
 
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct TestScalarMul {}
+    /// Unknown discriminators land here. Devnet builds route their maintenance
+    /// instructions through it so release builds and their IDL never contain them.
+    pub fn fallback<'info>(
+        program_id: &Pubkey,
+        accounts: &'info [AccountInfo<'info>],
+        data: &[u8],
+    ) -> Result<()> {
+        #[cfg(feature = "devnet")]
+        return devnet::dispatch(program_id, accounts, data);
+
+        #[cfg(not(feature = "devnet"))]
+        {
+            let _ = (program_id, accounts, data);
+            Err(anchor_lang::error::ErrorCode::InstructionFallbackNotFound.into())
+        }
+    }
+}
 
 #[derive(Accounts)]
 pub struct Initialize<'info> {
@@ -878,11 +1987,11 @@ pub struct Initialize<'info> {
     #[account(
         init,
         payer = admin,
-        space = MerkleTreeAccount::space(MAX_TREE_HEIGHT),
+        space = MerkleTreeAccount::LEN,
         seeds = [TREE_SEED],
         bump
     )]
-    pub merkle_tree: Account<'info, MerkleTreeAccount>,
+    pub merkle_tree: AccountLoader<'info, MerkleTreeAccount>,
     #[account(
         init,
         payer = admin,
@@ -894,669 +2003,1198 @@ pub struct Initialize<'info> {
     #[account(
         init,
         payer = admin,
-        space = VerifierAccount::space(),
-        seeds = [VERIFIER_SEED],
+        space = AdminConfig::LEN,
+        seeds = [ADMIN_CONFIG_SEED],
         bump
     )]
-    pub verifier: Account<'info, VerifierAccount>,
+    pub admin_config: Account<'info, AdminConfig>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct SetVerifier<'info> {
+pub struct MigrateGlobalState<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
-    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump, has_one = admin)]
-    pub global_state: Account<'info, GlobalState>,
-    #[account(mut, seeds = [VERIFIER_SEED], bump)]
-    pub verifier: Account<'info, VerifierAccount>,
+    /// CHECK: GlobalState PDA in the legacy layout; decoded and rewritten by the instruction
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = AdminConfig::LEN,
+        seeds = [ADMIN_CONFIG_SEED],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct SetFee<'info> {
+pub struct MigrateMerkleTree<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
-    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump = global_state.bump, has_one = admin)]
+    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump, has_one = admin)]
     pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [ADMIN_CONFIG_SEED], bump = admin_config.bump)]
+    pub admin_config: Account<'info, AdminConfig>,
+    /// CHECK: Merkle tree PDA in the legacy layout; decoded and rewritten by the instruction
+    #[account(mut, seeds = [TREE_SEED], bump)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct SetFeeCollector<'info> {
+#[instruction(circuit_id: CircuitId, version: u16)]
+pub struct InitVerifier<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
-    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump = global_state.bump, has_one = admin)]
-    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::VerifierAdmin) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(
+        init,
+        payer = admin,
+        space = VerifierAccount::LEN,
+        seeds = [VERIFIER_SEED, &[circuit_id as u8], &version.to_le_bytes()],
+        bump
+    )]
+    pub verifier: AccountLoader<'info, VerifierAccount>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ResetNullifiers<'info> {
-    #[account(mut)]
+#[instruction(circuit_id: CircuitId, version: u16)]
+pub struct AppendVerifierChunk<'info> {
     pub admin: Signer<'info>,
-    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump, has_one = admin)]
-    pub global_state: Account<'info, GlobalState>,
-    #[account(mut, seeds = [NULLIFIER_SEED], bump)]
-    pub nullifier_set: Account<'info, NullifierSetAccount>,
-}
-
-/// DEVNET ONLY: Emergency reset without admin check
-#[derive(Accounts)]
-pub struct EmergencyResetNullifiers<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, seeds = [NULLIFIER_SEED], bump)]
-    pub nullifier_set: Account<'info, NullifierSetAccount>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::VerifierAdmin) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(mut, seeds = [VERIFIER_SEED, &[circuit_id as u8], &version.to_le_bytes()], bump)]
+    pub verifier: AccountLoader<'info, VerifierAccount>,
 }
 
-/// Admin withdraw NOC from vault
 #[derive(Accounts)]
-pub struct AdminWithdrawVaultNoc<'info> {
-    #[account(mut)]
+#[instruction(circuit_id: CircuitId, version: u16)]
+pub struct CheckVerifierSubgroup<'info> {
     pub admin: Signer<'info>,
-    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump, has_one = admin)]
-    pub global_state: Account<'info, GlobalState>,
-    pub mint: Account<'info, Mint>,
-    /// CHECK: PDA authority for the vault
-    #[account(
-        seeds = [VAULT_AUTHORITY_SEED, mint.key().as_ref()],
-        bump
-    )]
-    pub vault_authority: UncheckedAccount<'info>,
     #[account(
-        mut,
-        token::mint = mint,
-        token::authority = vault_authority
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::VerifierAdmin) @ ShieldError::MissingRole
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
-    #[account(mut, token::mint = mint)]
-    pub destination: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(mut, seeds = [VERIFIER_SEED, &[circuit_id as u8], &version.to_le_bytes()], bump)]
+    pub verifier: AccountLoader<'info, VerifierAccount>,
 }
 
-/// Admin withdraw NOC from legacy vault (uses vault-authority PDA without mint suffix)
-/// DEVNET ONLY: No admin check for migration purposes
 #[derive(Accounts)]
-pub struct AdminWithdrawLegacyVault<'info> {
-    #[account(mut)]
+#[instruction(circuit_id: CircuitId, version: u16)]
+pub struct FinalizeVerifier<'info> {
     pub admin: Signer<'info>,
-    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
-    pub global_state: Account<'info, GlobalState>,
-    pub mint: Account<'info, Mint>,
-    /// CHECK: Legacy PDA authority (vault-authority without mint)
-    #[account(
-        seeds = [LEGACY_VAULT_AUTHORITY_SEED],
-        bump
-    )]
-    pub legacy_vault_authority: UncheckedAccount<'info>,
     #[account(
-        mut,
-        token::mint = mint,
-        token::authority = legacy_vault_authority
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::VerifierAdmin) @ ShieldError::MissingRole
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
-    #[account(mut, token::mint = mint)]
-    pub destination: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(mut, seeds = [VERIFIER_SEED, &[circuit_id as u8], &version.to_le_bytes()], bump)]
+    pub verifier: AccountLoader<'info, VerifierAccount>,
+    #[account(mut, seeds = [GOVERNANCE_SEED], bump = governance_queue.bump)]
+    pub governance_queue: Account<'info, GovernanceQueue>,
 }
 
 #[derive(Accounts)]
-pub struct TransparentDeposit<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+pub struct ProposeAdmin<'info> {
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump = global_state.bump, has_one = admin)]
     pub global_state: Account<'info, GlobalState>,
-    #[account(mut, seeds = [TREE_SEED], bump)]
-    pub merkle_tree: Account<'info, MerkleTreeAccount>,
-    #[account(seeds = [NULLIFIER_SEED], bump)]
-    pub nullifier_set: Account<'info, NullifierSetAccount>,
-    #[account(seeds = [VERIFIER_SEED], bump)]
-    pub verifier: Account<'info, VerifierAccount>,
-    pub mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(
-        init_if_needed,
-        payer = payer,
-        seeds = [VAULT_TOKEN_SEED, mint.key().as_ref()],
-            bump,
-        token::mint = mint,
-        token::authority = vault_authority
-    )]
-    pub vault_token_account: Account<'info, TokenAccount>,
-        #[account(
-            mut,
-            constraint = fee_collector_token_account.owner == global_state.fee_collector
-        )]
-        pub fee_collector_token_account: Account<'info, TokenAccount>,
-    /// CHECK: Derived PDA, used as token authority signer
-    #[account(
-        seeds = [VAULT_AUTHORITY_SEED, mint.key().as_ref()],
-            bump,
-    )]
-    pub vault_authority: UncheckedAccount<'info>,
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+    #[account(seeds = [ADMIN_CONFIG_SEED], bump = admin_config.bump)]
+    pub admin_config: Account<'info, AdminConfig>,
 }
 
-/// Accounts for transparent SOL deposit (native SOL, no token program)
 #[derive(Accounts)]
-pub struct TransparentDepositSol<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+pub struct AcceptAdmin<'info> {
+    pub pending_admin: Signer<'info>,
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump = global_state.bump, has_one = pending_admin)]
     pub global_state: Account<'info, GlobalState>,
-    #[account(mut, seeds = [TREE_SEED], bump)]
-    pub merkle_tree: Account<'info, MerkleTreeAccount>,
-    #[account(seeds = [NULLIFIER_SEED], bump)]
-    pub nullifier_set: Account<'info, NullifierSetAccount>,
-    #[account(seeds = [VERIFIER_SEED], bump)]
-    pub verifier: Account<'info, VerifierAccount>,
-    /// CHECK: SOL vault PDA, destination for native SOL
-    #[account(
-        mut,
-        seeds = [SOL_VAULT_SEED],
-        bump,
-    )]
-    pub sol_vault: UncheckedAccount<'info>,
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ShieldedTransfer<'info> {
-    #[account(mut, seeds = [TREE_SEED], bump)]
-    pub merkle_tree: Account<'info, MerkleTreeAccount>,
-    #[account(mut, seeds = [NULLIFIER_SEED], bump)]
-    pub nullifier_set: Account<'info, NullifierSetAccount>,
-    #[account(seeds = [TRANSFER_VERIFIER_SEED], bump)]
-    pub transfer_verifier: Account<'info, VerifierAccount>,
+pub struct SetAdminMultisig<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump, has_one = admin)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [ADMIN_CONFIG_SEED], bump = admin_config.bump)]
+    pub admin_config: Account<'info, AdminConfig>,
 }
 
 #[derive(Accounts)]
-pub struct SetTransferVerifier<'info> {
+#[instruction(holder: Pubkey)]
+pub struct GrantRole<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
-    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump, has_one = admin)]
     pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [ADMIN_CONFIG_SEED], bump = admin_config.bump)]
+    pub admin_config: Account<'info, AdminConfig>,
     #[account(
         init_if_needed,
         payer = admin,
-        space = VerifierAccount::space(),
-        seeds = [TRANSFER_VERIFIER_SEED],
+        space = RoleAccount::LEN,
+        seeds = [ROLE_SEED, holder.as_ref()],
         bump
     )]
-    pub transfer_verifier: Account<'info, VerifierAccount>,
+    pub role_account: Account<'info, RoleAccount>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct SetWithdrawVerifier<'info> {
-    #[account(mut)]
+#[instruction(holder: Pubkey)]
+pub struct RevokeRole<'info> {
     pub admin: Signer<'info>,
-    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump, has_one = admin)]
     pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [ADMIN_CONFIG_SEED], bump = admin_config.bump)]
+    pub admin_config: Account<'info, AdminConfig>,
+    #[account(mut, seeds = [ROLE_SEED, holder.as_ref()], bump = role_account.bump)]
+    pub role_account: Account<'info, RoleAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SetFee<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
     #[account(
-        init_if_needed,
-        payer = admin,
-        space = VerifierAccount::space(),
-        seeds = [WITHDRAW_VERIFIER_SEED],
-        bump
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::FeeAdmin) @ ShieldError::MissingRole
     )]
-    pub withdraw_verifier: Account<'info, VerifierAccount>,
-    pub system_program: Program<'info, System>,
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [GOVERNANCE_SEED], bump = governance_queue.bump)]
+    pub governance_queue: Account<'info, GovernanceQueue>,
 }
 
 #[derive(Accounts)]
-pub struct SetPartialWithdrawVerifier<'info> {
+pub struct InitializeGovernance<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
-    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump, has_one = admin)]
     pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [ADMIN_CONFIG_SEED], bump = admin_config.bump)]
+    pub admin_config: Account<'info, AdminConfig>,
     #[account(
-        init_if_needed,
+        init,
         payer = admin,
-        space = VerifierAccount::space(),
-        seeds = [PARTIAL_WITHDRAW_VERIFIER_SEED],
+        space = GovernanceQueue::LEN,
+        seeds = [GOVERNANCE_SEED],
         bump
     )]
-    pub partial_withdraw_verifier: Account<'info, VerifierAccount>,
+    pub governance_queue: Account<'info, GovernanceQueue>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct TransparentWithdraw<'info> {
-    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+pub struct ProposeGovernanceDelay<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump, has_one = admin)]
     pub global_state: Account<'info, GlobalState>,
-    #[account(mut, seeds = [NULLIFIER_SEED], bump)]
-    pub nullifier_set: Account<'info, NullifierSetAccount>,
-    #[account(seeds = [WITHDRAW_VERIFIER_SEED], bump)]
-    pub withdraw_verifier: Account<'info, VerifierAccount>,
-    pub mint: Account<'info, Mint>,
-    #[account(
-        mut,
-        seeds = [VAULT_TOKEN_SEED, mint.key().as_ref()],
-        bump,
-    )]
-    pub vault_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub receiver_token_account: Account<'info, TokenAccount>,
-    /// CHECK: Derived PDA
-    #[account(
-        seeds = [VAULT_AUTHORITY_SEED, mint.key().as_ref()],
-        bump,
-    )]
-    pub vault_authority: UncheckedAccount<'info>,
-    pub token_program: Program<'info, Token>,
+    #[account(seeds = [ADMIN_CONFIG_SEED], bump = admin_config.bump)]
+    pub admin_config: Account<'info, AdminConfig>,
+    #[account(mut, seeds = [GOVERNANCE_SEED], bump = governance_queue.bump)]
+    pub governance_queue: Account<'info, GovernanceQueue>,
 }
 
 #[derive(Accounts)]
-pub struct TransparentWithdrawSol<'info> {
-    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+pub struct ExecuteProposal<'info> {
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
-    #[account(mut, seeds = [NULLIFIER_SEED], bump)]
-    pub nullifier_set: Account<'info, NullifierSetAccount>,
-    #[account(seeds = [WITHDRAW_VERIFIER_SEED], bump)]
-    pub withdraw_verifier: Account<'info, VerifierAccount>,
-    /// CHECK: SOL vault PDA, source of native SOL
-    #[account(
-        mut,
-        seeds = [SOL_VAULT_SEED],
-        bump,
-    )]
-    pub sol_vault: UncheckedAccount<'info>,
-    /// CHECK: Recipient wallet address
-    #[account(mut)]
-    pub recipient: UncheckedAccount<'info>,
-    pub system_program: Program<'info, System>,
+    #[account(mut, seeds = [GOVERNANCE_SEED], bump = governance_queue.bump)]
+    pub governance_queue: Account<'info, GovernanceQueue>,
 }
 
 #[derive(Accounts)]
-pub struct PartialWithdraw<'info> {
-    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+pub struct ExecuteVerifierUpdate<'info> {
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
-    #[account(mut, seeds = [TREE_SEED], bump)]
-    pub merkle_tree: Account<'info, MerkleTreeAccount>,
-    #[account(mut, seeds = [NULLIFIER_SEED], bump)]
-    pub nullifier_set: Account<'info, NullifierSetAccount>,
-    #[account(seeds = [PARTIAL_WITHDRAW_VERIFIER_SEED], bump)]
-    pub partial_withdraw_verifier: Account<'info, VerifierAccount>,
-    pub mint: Account<'info, Mint>,
+    #[account(mut, seeds = [GOVERNANCE_SEED], bump = governance_queue.bump)]
+    pub governance_queue: Account<'info, GovernanceQueue>,
+    pub verifier: AccountLoader<'info, VerifierAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteFeeUpdate<'info> {
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [GOVERNANCE_SEED], bump = governance_queue.bump)]
+    pub governance_queue: Account<'info, GovernanceQueue>,
+    #[account(mut, seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    pub guardian: Signer<'info>,
     #[account(
-        mut,
-        seeds = [VAULT_TOKEN_SEED, mint.key().as_ref()],
-        bump,
+        seeds = [ROLE_SEED, guardian.key().as_ref()],
+        bump = guardian_role.bump,
+        constraint = guardian_role.has(Role::Guardian) @ ShieldError::MissingRole
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub guardian_role: Account<'info, RoleAccount>,
+    #[account(mut, seeds = [GOVERNANCE_SEED], bump = governance_queue.bump)]
+    pub governance_queue: Account<'info, GovernanceQueue>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeCollector<'info> {
     #[account(mut)]
-    pub receiver_token_account: Account<'info, TokenAccount>,
-    /// CHECK: Derived PDA
+    pub admin: Signer<'info>,
     #[account(
-        seeds = [VAULT_AUTHORITY_SEED, mint.key().as_ref()],
-        bump,
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::FeeAdmin) @ ShieldError::MissingRole
     )]
-    pub vault_authority: UncheckedAccount<'info>,
-    pub token_program: Program<'info, Token>,
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(mut, seeds = [GOVERNANCE_SEED], bump = governance_queue.bump)]
+    pub governance_queue: Account<'info, GovernanceQueue>,
 }
 
-// ============================================
-// SHIELDED POOL ACCOUNT STRUCTURES
-// ============================================
-
-const SHIELDED_POOL_SEED: &[u8] = b"shielded-pool";
-const SWAP_VERIFIER_SEED: &[u8] = b"swap-verifier";
-const SWAP_V2_VERIFIER_SEED: &[u8] = b"swap-v2-verifier";
-const CONSOLIDATE_VERIFIER_SEED: &[u8] = b"consolidate-verifier";
-
 #[derive(Accounts)]
-pub struct InitializeShieldedPool<'info> {
+pub struct InitializeFeeSchedule<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::FeeAdmin) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
     #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
     #[account(
         init,
         payer = admin,
-        space = ShieldedPool::LEN + 8,
-        seeds = [SHIELDED_POOL_SEED],
+        space = FeeSchedule::LEN,
+        seeds = [FEE_SCHEDULE_SEED],
         bump
     )]
-    pub shielded_pool: Account<'info, ShieldedPool>,
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    /// CHECK: SOL fee vault PDA, destination for native SOL fees
+    #[account(mut, seeds = [SOL_FEE_VAULT_SEED], bump)]
+    pub sol_fee_vault: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct SeedShieldedPool<'info> {
+pub struct InitFeeVault<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::Treasury) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [FEE_VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = fee_vault_authority
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Derived PDA, token authority of every fee vault
+    #[account(seeds = [FEE_VAULT_AUTHORITY_SEED], bump)]
+    pub fee_vault_authority: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::Treasury) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
     #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [FEE_VAULT_SEED, mint.key().as_ref()], bump)]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Derived PDA, token authority of every fee vault
+    #[account(seeds = [FEE_VAULT_AUTHORITY_SEED], bump)]
+    pub fee_vault_authority: UncheckedAccount<'info>,
     #[account(
         mut,
-        seeds = [SHIELDED_POOL_SEED],
-        bump = shielded_pool.bump,
-        constraint = shielded_pool.admin == admin.key()
+        token::mint = mint,
+        constraint = destination.owner == global_state.fee_collector @ ShieldError::Unauthorized
     )]
-    pub shielded_pool: Account<'info, ShieldedPool>,
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct AddPoolLiquidity<'info> {
-    #[account(mut)]
+pub struct WithdrawSolFees<'info> {
     pub admin: Signer<'info>,
     #[account(
-        mut,
-        seeds = [SHIELDED_POOL_SEED],
-        bump = shielded_pool.bump
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::Treasury) @ ShieldError::MissingRole
     )]
-    pub shielded_pool: Account<'info, ShieldedPool>,
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    /// CHECK: SOL fee vault PDA, destination for native SOL fees
+    #[account(mut, seeds = [SOL_FEE_VAULT_SEED], bump)]
+    pub sol_fee_vault: UncheckedAccount<'info>,
+    /// CHECK: Fee collector wallet from global state
+    #[account(mut, address = global_state.fee_collector @ ShieldError::Unauthorized)]
+    pub fee_collector: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct SetPoolReserves<'info> {
-    #[account(mut)]
+pub struct CollectPoolFees<'info> {
     pub admin: Signer<'info>,
     #[account(
-        mut,
-        seeds = [SHIELDED_POOL_SEED],
-        bump = shielded_pool.bump
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::Treasury) @ ShieldError::MissingRole
     )]
-    pub shielded_pool: Account<'info, ShieldedPool>,
+    pub admin_role: Account<'info, RoleAccount>,
+    /// CHECK: SOL vault PDA, holds the uncollected SOL swap fees
+    #[account(mut, seeds = [SOL_VAULT_SEED], bump)]
+    pub sol_vault: UncheckedAccount<'info>,
+    /// CHECK: SOL fee vault PDA, destination for native SOL fees
+    #[account(mut, seeds = [SOL_FEE_VAULT_SEED], bump)]
+    pub sol_fee_vault: UncheckedAccount<'info>,
+    pub noc_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [VAULT_TOKEN_SEED, noc_mint.key().as_ref()], bump)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Vault authority PDA
+    #[account(seeds = [VAULT_AUTHORITY_SEED, noc_mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(mut, seeds = [FEE_VAULT_SEED, noc_mint.key().as_ref()], bump)]
+    pub noc_fee_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [VAULT_LEDGER_SEED, NATIVE_MINT.as_ref()], bump = sol_ledger.bump)]
+    pub sol_ledger: Account<'info, VaultLedger>,
+    #[account(mut, seeds = [VAULT_LEDGER_SEED, noc_mint.key().as_ref()], bump = noc_ledger.bump)]
+    pub noc_ledger: Account<'info, VaultLedger>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ShieldedPoolSwap<'info> {
+pub struct MigrateLegacyNullifiers<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
     #[account(
-        mut,
-        seeds = [SHIELDED_POOL_SEED],
-        bump = shielded_pool.bump
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::Guardian) @ ShieldError::MissingRole
     )]
-    pub shielded_pool: Account<'info, ShieldedPool>,
-    #[account(mut, seeds = [TREE_SEED], bump)]
-    pub merkle_tree: Account<'info, MerkleTreeAccount>,
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
     #[account(mut, seeds = [NULLIFIER_SEED], bump)]
     pub nullifier_set: Account<'info, NullifierSetAccount>,
-    #[account(seeds = [SWAP_VERIFIER_SEED], bump)]
-    pub swap_verifier: Account<'info, VerifierAccount>,
+    pub system_program: Program<'info, System>,
 }
 
-/// Accounts for swap V2 - supports partial swaps with change
 #[derive(Accounts)]
-pub struct ShieldedPoolSwapV2<'info> {
+pub struct MigrateLegacyVault<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::Treasury) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Legacy PDA authority (vault-authority without mint)
+    #[account(seeds = [LEGACY_VAULT_AUTHORITY_SEED], bump)]
+    pub legacy_vault_authority: UncheckedAccount<'info>,
     #[account(
         mut,
-        seeds = [SHIELDED_POOL_SEED],
-        bump = shielded_pool.bump
+        token::mint = mint,
+        token::authority = legacy_vault_authority
     )]
-    pub shielded_pool: Account<'info, ShieldedPool>,
-    #[account(mut, seeds = [TREE_SEED], bump)]
-    pub merkle_tree: Account<'info, MerkleTreeAccount>,
-    #[account(mut, seeds = [NULLIFIER_SEED], bump)]
-    pub nullifier_set: Account<'info, NullifierSetAccount>,
-    #[account(seeds = [SWAP_V2_VERIFIER_SEED], bump)]
-    pub swap_v2_verifier: Account<'info, VerifierAccount>,
+    pub legacy_vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [VAULT_TOKEN_SEED, mint.key().as_ref()], bump)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [VAULT_LEDGER_SEED, mint.key().as_ref()], bump = ledger.bump)]
+    pub ledger: Account<'info, VaultLedger>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct GetPoolReserves<'info> {
-    #[account(seeds = [SHIELDED_POOL_SEED], bump = shielded_pool.bump)]
-    pub shielded_pool: Account<'info, ShieldedPool>,
+pub struct SweepVaultSurplus<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::Treasury) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: PDA authority for the vault
+    #[account(seeds = [VAULT_AUTHORITY_SEED, mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(mut, seeds = [VAULT_TOKEN_SEED, mint.key().as_ref()], bump)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [VAULT_LEDGER_SEED, mint.key().as_ref()], bump = ledger.bump)]
+    pub ledger: Account<'info, VaultLedger>,
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = destination.owner == global_state.fee_collector @ ShieldError::Unauthorized
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
-/// Accounts for transparent (non-shielded) pool swap
 #[derive(Accounts)]
-pub struct TransparentPoolSwap<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+pub struct SweepSolVaultSurplus<'info> {
+    pub admin: Signer<'info>,
     #[account(
-        mut,
-        seeds = [SHIELDED_POOL_SEED],
-        bump = shielded_pool.bump
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::Treasury) @ ShieldError::MissingRole
     )]
-    pub shielded_pool: Account<'info, ShieldedPool>,
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
     /// CHECK: SOL vault PDA
+    #[account(mut, seeds = [SOL_VAULT_SEED], bump)]
+    pub sol_vault: UncheckedAccount<'info>,
+    #[account(mut, seeds = [VAULT_LEDGER_SEED, NATIVE_MINT.as_ref()], bump = ledger.bump)]
+    pub ledger: Account<'info, VaultLedger>,
+    /// CHECK: Fee collector wallet from global state
+    #[account(mut, address = global_state.fee_collector @ ShieldError::Unauthorized)]
+    pub fee_collector: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint_field: [u8; 32])]
+pub struct RegisterAsset<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
     #[account(
-        mut,
-        seeds = [SOL_VAULT_SEED],
-        bump,
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::Treasury) @ ShieldError::MissingRole
     )]
-    pub sol_vault: UncheckedAccount<'info>,
-    pub noc_mint: Account<'info, Mint>,
+    pub admin_role: Account<'info, RoleAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
     #[account(
-        mut,
-        seeds = [VAULT_TOKEN_SEED, noc_mint.key().as_ref()],
-        bump,
+        init,
+        payer = admin,
+        space = SupportedAsset::LEN,
+        seeds = [ASSET_SEED, mint.key().as_ref()],
+        bump
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
-    /// CHECK: Vault authority PDA
+    pub asset: Account<'info, SupportedAsset>,
+    /// Seeded by the canonical little-endian argument, so each field value has one record
     #[account(
-        seeds = [VAULT_AUTHORITY_SEED, noc_mint.key().as_ref()],
-        bump,
+        init,
+        payer = admin,
+        space = MintFieldRecord::LEN,
+        seeds = [MINT_FIELD_SEED, mint_field.as_ref()],
+        bump
     )]
-    pub vault_authority: UncheckedAccount<'info>,
+    pub mint_field_record: Account<'info, MintFieldRecord>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetAssetConfig<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::Treasury) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(mut, seeds = [ASSET_SEED, asset.mint.as_ref()], bump = asset.bump)]
+    pub asset: Account<'info, SupportedAsset>,
+}
+
+#[derive(Accounts)]
+pub struct InitVaultLedger<'info> {
     #[account(mut)]
-    pub user_noc_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::Treasury) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(seeds = [ASSET_SEED, asset.mint.as_ref()], bump = asset.bump)]
+    pub asset: Account<'info, SupportedAsset>,
+    #[account(seeds = [VAULT_TOKEN_SEED, asset.mint.as_ref()], bump)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = admin,
+        space = VaultLedger::LEN,
+        seeds = [VAULT_LEDGER_SEED, asset.mint.as_ref()],
+        bump
+    )]
+    pub ledger: Account<'info, VaultLedger>,
     pub system_program: Program<'info, System>,
 }
 
-/// Accounts for admin vault initialization
 #[derive(Accounts)]
-pub struct InitTokenVault<'info> {
+pub struct InitSolVaultLedger<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::Treasury) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
+    /// CHECK: SOL vault PDA, read for its balance and funded with its rent-exempt reserve
+    #[account(mut, seeds = [SOL_VAULT_SEED], bump)]
+    pub sol_vault: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = VaultLedger::LEN,
+        seeds = [VAULT_LEDGER_SEED, NATIVE_MINT.as_ref()],
+        bump
+    )]
+    pub ledger: Account<'info, VaultLedger>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransparentDeposit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
     #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
-    pub mint: Account<'info, Mint>,
+    #[account(mut, seeds = [TREE_SEED], bump)]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeAccount>,
+    #[account(seeds = [NULLIFIER_SEED], bump)]
+    pub nullifier_set: Account<'info, NullifierSetAccount>,
+    #[account(constraint = global_state.is_active_verifier(&*verifier.load()?, CircuitId::Deposit) @ ShieldError::VerifierMissing)]
+    pub verifier: AccountLoader<'info, VerifierAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [ASSET_SEED, mint.key().as_ref()], bump = asset.bump)]
+    pub asset: Account<'info, SupportedAsset>,
+    #[account(mut, seeds = [VAULT_LEDGER_SEED, mint.key().as_ref()], bump = ledger.bump)]
+    pub ledger: Account<'info, VaultLedger>,
+    #[account(mut)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
     #[account(
         init_if_needed,
-        payer = admin,
+        payer = payer,
         seeds = [VAULT_TOKEN_SEED, mint.key().as_ref()],
-        bump,
+            bump,
         token::mint = mint,
         token::authority = vault_authority
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
-    /// CHECK: Derived PDA, used as token authority
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    #[account(mut, seeds = [FEE_VAULT_SEED, mint.key().as_ref()], bump)]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Derived PDA, used as token authority signer
     #[account(
         seeds = [VAULT_AUTHORITY_SEED, mint.key().as_ref()],
-        bump,
+            bump,
     )]
     pub vault_authority: UncheckedAccount<'info>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+/// Accounts for transparent SOL deposit (native SOL, no token program)
 #[derive(Accounts)]
-pub struct SetSwapVerifier<'info> {
+pub struct TransparentDepositSol<'info> {
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub payer: Signer<'info>,
     #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [TREE_SEED], bump)]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeAccount>,
+    #[account(seeds = [NULLIFIER_SEED], bump)]
+    pub nullifier_set: Account<'info, NullifierSetAccount>,
+    #[account(constraint = global_state.is_active_verifier(&*verifier.load()?, CircuitId::Deposit) @ ShieldError::VerifierMissing)]
+    pub verifier: AccountLoader<'info, VerifierAccount>,
+    /// CHECK: SOL vault PDA, destination for native SOL
     #[account(
-        init_if_needed,
-        payer = admin,
-        space = VerifierAccount::space(),
-        seeds = [SWAP_VERIFIER_SEED],
-        bump
+        mut,
+        seeds = [SOL_VAULT_SEED],
+        bump,
     )]
-    pub swap_verifier: Account<'info, VerifierAccount>,
+    pub sol_vault: UncheckedAccount<'info>,
+    #[account(mut, seeds = [ASSET_SEED, NATIVE_MINT.as_ref()], bump = asset.bump)]
+    pub asset: Account<'info, SupportedAsset>,
+    #[account(mut, seeds = [VAULT_LEDGER_SEED, NATIVE_MINT.as_ref()], bump = ledger.bump)]
+    pub ledger: Account<'info, VaultLedger>,
+    #[account(seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    /// CHECK: SOL fee vault PDA, destination for native SOL fees
+    #[account(mut, seeds = [SOL_FEE_VAULT_SEED], bump)]
+    pub sol_fee_vault: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct InitSwapVerifierChunked<'info> {
+pub struct ShieldedTransfer<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub payer: Signer<'info>,
+    #[account(mut, seeds = [TREE_SEED], bump)]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeAccount>,
+    /// Legacy nullifier set, read until `migrate_legacy_nullifiers` has drained it
+    #[account(seeds = [NULLIFIER_SEED], bump)]
+    pub nullifier_set: Account<'info, NullifierSetAccount>,
+    #[account(constraint = global_state.is_active_verifier(&*transfer_verifier.load()?, CircuitId::Transfer) @ ShieldError::VerifierMissing)]
+    pub transfer_verifier: AccountLoader<'info, VerifierAccount>,
+    #[account(seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    /// CHECK: SOL fee vault PDA, destination for native SOL fees
+    #[account(mut, seeds = [SOL_FEE_VAULT_SEED], bump)]
+    pub sol_fee_vault: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransparentWithdraw<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
     #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [TREE_SEED], bump)]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeAccount>,
+    /// Legacy nullifier set, read until `migrate_legacy_nullifiers` has drained it
+    #[account(seeds = [NULLIFIER_SEED], bump)]
+    pub nullifier_set: Account<'info, NullifierSetAccount>,
+    /// CHECK: NullifierRecord PDA for the spent nullifier, created by consume_nullifier
+    #[account(mut)]
+    pub nullifier_record: UncheckedAccount<'info>,
+    #[account(constraint = global_state.is_active_verifier(&*withdraw_verifier.load()?, CircuitId::Withdraw) @ ShieldError::VerifierMissing)]
+    pub withdraw_verifier: AccountLoader<'info, VerifierAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [ASSET_SEED, mint.key().as_ref()], bump = asset.bump)]
+    pub asset: Account<'info, SupportedAsset>,
+    #[account(mut, seeds = [VAULT_LEDGER_SEED, mint.key().as_ref()], bump = ledger.bump)]
+    pub ledger: Account<'info, VaultLedger>,
     #[account(
-        init_if_needed,
-        payer = admin,
-        space = VerifierAccount::space(),
-        seeds = [SWAP_VERIFIER_SEED],
-        bump
+        mut,
+        seeds = [VAULT_TOKEN_SEED, mint.key().as_ref()],
+        bump,
     )]
-    pub swap_verifier: Account<'info, VerifierAccount>,
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub receiver_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// Receives `relayer_fee`; required only when it is non-zero
+    #[account(mut)]
+    pub relayer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: Derived PDA
+    #[account(
+        seeds = [VAULT_AUTHORITY_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    #[account(mut, seeds = [FEE_VAULT_SEED, mint.key().as_ref()], bump)]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct AppendSwapVerifierChunk<'info> {
+pub struct TransparentWithdrawSol<'info> {
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub payer: Signer<'info>,
     #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [TREE_SEED], bump)]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeAccount>,
+    /// Legacy nullifier set, read until `migrate_legacy_nullifiers` has drained it
+    #[account(seeds = [NULLIFIER_SEED], bump)]
+    pub nullifier_set: Account<'info, NullifierSetAccount>,
+    /// CHECK: NullifierRecord PDA for the spent nullifier, created by consume_nullifier
+    #[account(mut)]
+    pub nullifier_record: UncheckedAccount<'info>,
+    #[account(constraint = global_state.is_active_verifier(&*withdraw_verifier.load()?, CircuitId::Withdraw) @ ShieldError::VerifierMissing)]
+    pub withdraw_verifier: AccountLoader<'info, VerifierAccount>,
+    /// CHECK: SOL vault PDA, source of native SOL
     #[account(
         mut,
-        seeds = [SWAP_VERIFIER_SEED],
-        bump
+        seeds = [SOL_VAULT_SEED],
+        bump,
     )]
-    pub swap_verifier: Account<'info, VerifierAccount>,
+    pub sol_vault: UncheckedAccount<'info>,
+    #[account(mut, seeds = [ASSET_SEED, NATIVE_MINT.as_ref()], bump = asset.bump)]
+    pub asset: Account<'info, SupportedAsset>,
+    #[account(mut, seeds = [VAULT_LEDGER_SEED, NATIVE_MINT.as_ref()], bump = ledger.bump)]
+    pub ledger: Account<'info, VaultLedger>,
+    #[account(seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    /// CHECK: SOL fee vault PDA, destination for native SOL fees
+    #[account(mut, seeds = [SOL_FEE_VAULT_SEED], bump)]
+    pub sol_fee_vault: UncheckedAccount<'info>,
+    /// CHECK: Recipient wallet address
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    /// CHECK: Relayer wallet, checked against the `relayer` argument; required only when `relayer_fee` is non-zero
+    #[account(mut)]
+    pub relayer: Option<UncheckedAccount<'info>>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct FinalizeSwapVerifier<'info> {
+pub struct PartialWithdraw<'info> {
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub payer: Signer<'info>,
     #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [TREE_SEED], bump)]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeAccount>,
+    /// Legacy nullifier set, read until `migrate_legacy_nullifiers` has drained it
+    #[account(seeds = [NULLIFIER_SEED], bump)]
+    pub nullifier_set: Account<'info, NullifierSetAccount>,
+    /// CHECK: NullifierRecord PDA for the spent nullifier, created by consume_nullifier
+    #[account(mut)]
+    pub nullifier_record: UncheckedAccount<'info>,
+    #[account(constraint = global_state.is_active_verifier(&*partial_withdraw_verifier.load()?, CircuitId::PartialWithdraw) @ ShieldError::VerifierMissing)]
+    pub partial_withdraw_verifier: AccountLoader<'info, VerifierAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [ASSET_SEED, mint.key().as_ref()], bump = asset.bump)]
+    pub asset: Account<'info, SupportedAsset>,
+    #[account(mut, seeds = [VAULT_LEDGER_SEED, mint.key().as_ref()], bump = ledger.bump)]
+    pub ledger: Account<'info, VaultLedger>,
     #[account(
-        seeds = [SWAP_VERIFIER_SEED],
-        bump
+        mut,
+        seeds = [VAULT_TOKEN_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub receiver_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// Receives `relayer_fee`; required only when it is non-zero
+    #[account(mut)]
+    pub relayer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: Derived PDA
+    #[account(
+        seeds = [VAULT_AUTHORITY_SEED, mint.key().as_ref()],
+        bump,
     )]
-    pub swap_verifier: Account<'info, VerifierAccount>,
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    #[account(mut, seeds = [FEE_VAULT_SEED, mint.key().as_ref()], bump)]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 // ============================================
-// SWAP V2 VERIFIER ACCOUNT STRUCTURES
+// SHIELDED POOL ACCOUNT STRUCTURES
 // ============================================
 
+const SHIELDED_POOL_SEED: &[u8] = b"shielded-pool";
+const POOL_SOL_VAULT_SEED: &[u8] = b"pool-sol-vault";
+const POOL_TOKEN_VAULT_SEED: &[u8] = b"pool-token-vault";
+
 #[derive(Accounts)]
-pub struct SetSwapV2Verifier<'info> {
+pub struct InitializeShieldedPool<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::PoolAdmin) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
     #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
     #[account(
-        init_if_needed,
+        init,
         payer = admin,
-        space = VerifierAccount::space(),
-        seeds = [SWAP_V2_VERIFIER_SEED],
+        space = ShieldedPool::LEN + 8,
+        seeds = [SHIELDED_POOL_SEED],
         bump
     )]
-    pub swap_v2_verifier: Account<'info, VerifierAccount>,
+    pub shielded_pool: Account<'info, ShieldedPool>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct InitSwapV2VerifierChunked<'info> {
+pub struct InitPoolVaults<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
-    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
-    pub global_state: Account<'info, GlobalState>,
     #[account(
-        init_if_needed,
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::PoolAdmin) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(seeds = [SHIELDED_POOL_SEED], bump = shielded_pool.bump)]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+    pub noc_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Pool SOL vault PDA, funded here to its rent-exempt minimum
+    #[account(mut, seeds = [POOL_SOL_VAULT_SEED], bump)]
+    pub pool_sol_vault: UncheckedAccount<'info>,
+    #[account(
+        init,
         payer = admin,
-        space = VerifierAccount::space(),
-        seeds = [SWAP_V2_VERIFIER_SEED],
-        bump
+        seeds = [POOL_TOKEN_VAULT_SEED],
+        bump,
+        token::mint = noc_mint,
+        token::authority = shielded_pool
     )]
-    pub swap_v2_verifier: Account<'info, VerifierAccount>,
+    pub pool_token_vault: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct AppendSwapV2VerifierChunk<'info> {
+pub struct SeedShieldedPool<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::PoolAdmin) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
     #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
     #[account(
         mut,
-        seeds = [SWAP_V2_VERIFIER_SEED],
-        bump
+        seeds = [SHIELDED_POOL_SEED],
+        bump = shielded_pool.bump
     )]
-    pub swap_v2_verifier: Account<'info, VerifierAccount>,
+    pub shielded_pool: Account<'info, ShieldedPool>,
+    pub noc_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Pool SOL vault PDA, holds the pool's SOL reserve
+    #[account(mut, seeds = [POOL_SOL_VAULT_SEED], bump)]
+    pub pool_sol_vault: UncheckedAccount<'info>,
+    #[account(mut, seeds = [POOL_TOKEN_VAULT_SEED], bump, token::mint = noc_mint)]
+    pub pool_token_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub admin_noc_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct FinalizeSwapV2Verifier<'info> {
+pub struct AddPoolLiquidity<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::PoolAdmin) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
+    #[account(
+        mut,
+        seeds = [SHIELDED_POOL_SEED],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+    pub noc_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Pool SOL vault PDA, holds the pool's SOL reserve
+    #[account(mut, seeds = [POOL_SOL_VAULT_SEED], bump)]
+    pub pool_sol_vault: UncheckedAccount<'info>,
+    #[account(mut, seeds = [POOL_TOKEN_VAULT_SEED], bump, token::mint = noc_mint)]
+    pub pool_token_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub admin_noc_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncPoolReserves<'info> {
     #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
     #[account(
-        seeds = [SWAP_V2_VERIFIER_SEED],
-        bump
+        mut,
+        seeds = [SHIELDED_POOL_SEED],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+    pub noc_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Pool SOL vault PDA, holds the pool's SOL reserve
+    #[account(mut, seeds = [POOL_SOL_VAULT_SEED], bump)]
+    pub pool_sol_vault: UncheckedAccount<'info>,
+    #[account(mut, seeds = [POOL_TOKEN_VAULT_SEED], bump, token::mint = noc_mint)]
+    pub pool_token_vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Fee collector wallet from global state, receives skimmed SOL
+    #[account(mut, address = global_state.fee_collector @ ShieldError::Unauthorized)]
+    pub fee_collector: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        token::mint = noc_mint,
+        constraint = treasury_noc_account.owner == global_state.fee_collector @ ShieldError::Unauthorized
     )]
-    pub swap_v2_verifier: Account<'info, VerifierAccount>,
+    pub treasury_noc_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
-// ============================================
-// CONSOLIDATE VERIFIER ACCOUNT STRUCTURES
-// ============================================
-
 #[derive(Accounts)]
-pub struct InitConsolidateVerifierChunked<'info> {
-    #[account(mut)]
-    pub admin: Signer<'info>,
+pub struct ShieldedPoolSwap<'info> {
     #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
     #[account(
-        init_if_needed,
-        payer = admin,
-        space = VerifierAccount::space(),
-        seeds = [CONSOLIDATE_VERIFIER_SEED],
-        bump
+        mut,
+        seeds = [SHIELDED_POOL_SEED],
+        bump = shielded_pool.bump
     )]
-    pub consolidate_verifier: Account<'info, VerifierAccount>,
+    pub shielded_pool: Account<'info, ShieldedPool>,
+    #[account(mut, seeds = [TREE_SEED], bump)]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeAccount>,
+    /// Legacy nullifier set, read until `migrate_legacy_nullifiers` has drained it
+    #[account(seeds = [NULLIFIER_SEED], bump)]
+    pub nullifier_set: Account<'info, NullifierSetAccount>,
+    /// CHECK: NullifierRecord PDA for the spent nullifier, created by consume_nullifier
+    #[account(mut)]
+    pub nullifier_record: UncheckedAccount<'info>,
+    #[account(constraint = global_state.is_active_verifier(&*swap_verifier.load()?, CircuitId::Swap) @ ShieldError::VerifierMissing)]
+    pub swap_verifier: AccountLoader<'info, VerifierAccount>,
+    #[account(seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    /// The pool's NOC mint; keys the swap fee of NOC input
+    pub noc_mint: InterfaceAccount<'info, Mint>,
+    #[account(seeds = [ASSET_SEED, NATIVE_MINT.as_ref()], bump = sol_asset.bump)]
+    pub sol_asset: Account<'info, SupportedAsset>,
+    #[account(seeds = [ASSET_SEED, noc_mint.key().as_ref()], bump = noc_asset.bump)]
+    pub noc_asset: Account<'info, SupportedAsset>,
+    #[account(mut, seeds = [VAULT_LEDGER_SEED, NATIVE_MINT.as_ref()], bump = sol_ledger.bump)]
+    pub sol_ledger: Account<'info, VaultLedger>,
+    #[account(mut, seeds = [VAULT_LEDGER_SEED, noc_mint.key().as_ref()], bump = noc_ledger.bump)]
+    pub noc_ledger: Account<'info, VaultLedger>,
+    /// CHECK: SOL vault PDA, backs SOL notes
+    #[account(mut, seeds = [SOL_VAULT_SEED], bump)]
+    pub sol_vault: UncheckedAccount<'info>,
+    #[account(mut, seeds = [VAULT_TOKEN_SEED, noc_mint.key().as_ref()], bump)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Vault authority PDA
+    #[account(seeds = [VAULT_AUTHORITY_SEED, noc_mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    /// CHECK: Pool SOL vault PDA, holds the pool's SOL reserve
+    #[account(mut, seeds = [POOL_SOL_VAULT_SEED], bump)]
+    pub pool_sol_vault: UncheckedAccount<'info>,
+    #[account(mut, seeds = [POOL_TOKEN_VAULT_SEED], bump, token::mint = noc_mint)]
+    pub pool_token_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
+/// Accounts for swap V2 - supports partial swaps with change
 #[derive(Accounts)]
-pub struct AppendConsolidateVerifierChunk<'info> {
-    #[account(mut)]
-    pub admin: Signer<'info>,
+pub struct ShieldedPoolSwapV2<'info> {
     #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
     #[account(
         mut,
-        seeds = [CONSOLIDATE_VERIFIER_SEED],
-        bump
+        seeds = [SHIELDED_POOL_SEED],
+        bump = shielded_pool.bump
     )]
-    pub consolidate_verifier: Account<'info, VerifierAccount>,
+    pub shielded_pool: Account<'info, ShieldedPool>,
+    #[account(mut, seeds = [TREE_SEED], bump)]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeAccount>,
+    /// Legacy nullifier set, read until `migrate_legacy_nullifiers` has drained it
+    #[account(seeds = [NULLIFIER_SEED], bump)]
+    pub nullifier_set: Account<'info, NullifierSetAccount>,
+    /// CHECK: NullifierRecord PDA for the spent nullifier, created by consume_nullifier
+    #[account(mut)]
+    pub nullifier_record: UncheckedAccount<'info>,
+    #[account(constraint = global_state.is_active_verifier(&*swap_v2_verifier.load()?, CircuitId::SwapV2) @ ShieldError::VerifierMissing)]
+    pub swap_v2_verifier: AccountLoader<'info, VerifierAccount>,
+    #[account(seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    /// The pool's NOC mint; keys the swap fee of NOC input
+    pub noc_mint: InterfaceAccount<'info, Mint>,
+    #[account(seeds = [ASSET_SEED, NATIVE_MINT.as_ref()], bump = sol_asset.bump)]
+    pub sol_asset: Account<'info, SupportedAsset>,
+    #[account(seeds = [ASSET_SEED, noc_mint.key().as_ref()], bump = noc_asset.bump)]
+    pub noc_asset: Account<'info, SupportedAsset>,
+    #[account(mut, seeds = [VAULT_LEDGER_SEED, NATIVE_MINT.as_ref()], bump = sol_ledger.bump)]
+    pub sol_ledger: Account<'info, VaultLedger>,
+    #[account(mut, seeds = [VAULT_LEDGER_SEED, noc_mint.key().as_ref()], bump = noc_ledger.bump)]
+    pub noc_ledger: Account<'info, VaultLedger>,
+    /// CHECK: SOL vault PDA, backs SOL notes
+    #[account(mut, seeds = [SOL_VAULT_SEED], bump)]
+    pub sol_vault: UncheckedAccount<'info>,
+    #[account(mut, seeds = [VAULT_TOKEN_SEED, noc_mint.key().as_ref()], bump)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Vault authority PDA
+    #[account(seeds = [VAULT_AUTHORITY_SEED, noc_mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    /// CHECK: Pool SOL vault PDA, holds the pool's SOL reserve
+    #[account(mut, seeds = [POOL_SOL_VAULT_SEED], bump)]
+    pub pool_sol_vault: UncheckedAccount<'info>,
+    #[account(mut, seeds = [POOL_TOKEN_VAULT_SEED], bump, token::mint = noc_mint)]
+    pub pool_token_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct FinalizeConsolidateVerifier<'info> {
+pub struct GetPoolReserves<'info> {
+    #[account(seeds = [SHIELDED_POOL_SEED], bump = shielded_pool.bump)]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+}
+
+/// Accounts for transparent (non-shielded) pool swap
+#[derive(Accounts)]
+pub struct TransparentPoolSwap<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SHIELDED_POOL_SEED],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+    pub noc_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Pool SOL vault PDA, holds the pool's SOL reserve
+    #[account(mut, seeds = [POOL_SOL_VAULT_SEED], bump)]
+    pub pool_sol_vault: UncheckedAccount<'info>,
+    #[account(mut, seeds = [POOL_TOKEN_VAULT_SEED], bump, token::mint = noc_mint)]
+    pub pool_token_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_noc_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    /// CHECK: SOL fee vault PDA, destination for native SOL fees
+    #[account(mut, seeds = [SOL_FEE_VAULT_SEED], bump)]
+    pub sol_fee_vault: UncheckedAccount<'info>,
+    #[account(mut, seeds = [FEE_VAULT_SEED, noc_mint.key().as_ref()], bump)]
+    pub noc_fee_vault: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for admin vault initialization
+#[derive(Accounts)]
+pub struct InitTokenVault<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
+    #[account(
+        seeds = [ROLE_SEED, admin.key().as_ref()],
+        bump = admin_role.bump,
+        constraint = admin_role.has(Role::Treasury) @ ShieldError::MissingRole
+    )]
+    pub admin_role: Account<'info, RoleAccount>,
     #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
     pub global_state: Account<'info, GlobalState>,
+    pub mint: InterfaceAccount<'info, Mint>,
     #[account(
-        seeds = [CONSOLIDATE_VERIFIER_SEED],
-        bump
+        init_if_needed,
+        payer = admin,
+        seeds = [VAULT_TOKEN_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault_authority
     )]
-    pub consolidate_verifier: Account<'info, VerifierAccount>,
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Derived PDA, used as token authority
+    #[account(
+        seeds = [VAULT_AUTHORITY_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
 pub struct ShieldedConsolidate<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
     #[account(
         mut,
         seeds = [TREE_SEED],
         bump
     )]
-    pub merkle_tree: Account<'info, MerkleTreeAccount>,
+    pub merkle_tree: AccountLoader<'info, MerkleTreeAccount>,
+    /// Legacy nullifier set, read until `migrate_legacy_nullifiers` has drained it
     #[account(
-        mut,
         seeds = [NULLIFIER_SEED],
         bump
     )]
     pub nullifier_set: Account<'info, NullifierSetAccount>,
-    #[account(
-        seeds = [CONSOLIDATE_VERIFIER_SEED],
-        bump
-    )]
-    pub consolidate_verifier: Account<'info, VerifierAccount>,
+    #[account(constraint = global_state.is_active_verifier(&*consolidate_verifier.load()?, CircuitId::Consolidate) @ ShieldError::VerifierMissing)]
+    pub consolidate_verifier: AccountLoader<'info, VerifierAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg(all(test, not(feature = "devnet")))]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::hash::hash;
+
+    const DEVNET_ONLY: [&str; 3] = ["reset_nullifiers", "emergency_reset_nullifiers", "test_scalar_mul"];
+
+    #[test]
+    fn release_build_rejects_devnet_discriminators() {
+        for name in DEVNET_ONLY {
+            let data = hash(format!("global:{}", name).as_bytes()).to_bytes();
+            let err = entry(&ID, &[], &data[..8]).unwrap_err();
+            assert_eq!(
+                err,
+                ProgramError::Custom(anchor_lang::error::ErrorCode::InstructionFallbackNotFound as u32)
+            );
+        }
+    }
+
+    #[test]
+    fn release_idl_omits_devnet_instructions() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../app/src/lib/idl/noctura_shield.json");
+        let idl: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let names: Vec<&str> = idl["instructions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|ix| ix["name"].as_str().unwrap())
+            .collect();
+        assert!(names.contains(&"initialize"));
+        for name in DEVNET_ONLY {
+            let camel: String = name
+                .split('_')
+                .enumerate()
+                .map(|(i, part)| if i == 0 { part.to_string() } else { part[..1].to_uppercase() + &part[1..] })
+                .collect();
+            assert!(!names.contains(&name), "IDL exposes {}", name);
+            assert!(!names.contains(&camel.as_str()), "IDL exposes {}", camel);
+        }
+    }
 }
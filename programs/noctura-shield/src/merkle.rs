@@ -1,37 +1,164 @@
+use std::cell::{Ref, RefMut};
+
 use anchor_lang::prelude::*;
 
 use crate::{
     errors::ShieldError,
-    state::{MerkleTreeAccount, MAX_ROOT_HISTORY},
+    public_inputs::canonical_field_from_le,
+    state::{MerkleTreeAccount, MAX_ROOT_HISTORY, MAX_TREE_HEIGHT},
     utils::hash_nodes,
 };
+#[cfg(feature = "legacy-keccak-tree")]
+use crate::{state::LegacyMerkleTreeAccount, utils::legacy_hash_nodes};
+
+/// Poseidon(2) zero hashes for an empty leaf of 0, as in `zk/witness/merkle.ts`.
+/// Level `i` is the root of an empty subtree of height `i`; level 20 is the empty tree root.
+const POSEIDON_ZERO_HASHES: [[u8; 32]; MAX_TREE_HEIGHT as usize + 1] = [
+    // level 0
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+    // level 1
+    [
+        0x20, 0x98, 0xf5, 0xfb, 0x9e, 0x23, 0x9e, 0xab, 0x3c, 0xea, 0xc3, 0xf2, 0x7b, 0x81, 0xe4, 0x81,
+        0xdc, 0x31, 0x24, 0xd5, 0x5f, 0xfe, 0xd5, 0x23, 0xa8, 0x39, 0xee, 0x84, 0x46, 0xb6, 0x48, 0x64,
+    ],
+    // level 2
+    [
+        0x10, 0x69, 0x67, 0x3d, 0xcd, 0xb1, 0x22, 0x63, 0xdf, 0x30, 0x1a, 0x6f, 0xf5, 0x84, 0xa7, 0xec,
+        0x26, 0x1a, 0x44, 0xcb, 0x9d, 0xc6, 0x8d, 0xf0, 0x67, 0xa4, 0x77, 0x44, 0x60, 0xb1, 0xf1, 0xe1,
+    ],
+    // level 3
+    [
+        0x18, 0xf4, 0x33, 0x31, 0x53, 0x7e, 0xe2, 0xaf, 0x2e, 0x3d, 0x75, 0x8d, 0x50, 0xf7, 0x21, 0x06,
+        0x46, 0x7c, 0x6e, 0xea, 0x50, 0x37, 0x1d, 0xd5, 0x28, 0xd5, 0x7e, 0xb2, 0xb8, 0x56, 0xd2, 0x38,
+    ],
+    // level 4
+    [
+        0x07, 0xf9, 0xd8, 0x37, 0xcb, 0x17, 0xb0, 0xd3, 0x63, 0x20, 0xff, 0xe9, 0x3b, 0xa5, 0x23, 0x45,
+        0xf1, 0xb7, 0x28, 0x57, 0x1a, 0x56, 0x82, 0x65, 0xca, 0xac, 0x97, 0x55, 0x9d, 0xbc, 0x95, 0x2a,
+    ],
+    // level 5
+    [
+        0x2b, 0x94, 0xcf, 0x5e, 0x87, 0x46, 0xb3, 0xf5, 0xc9, 0x63, 0x1f, 0x4c, 0x5d, 0xf3, 0x29, 0x07,
+        0xa6, 0x99, 0xc5, 0x8c, 0x94, 0xb2, 0xad, 0x4d, 0x7b, 0x5c, 0xec, 0x16, 0x39, 0x18, 0x3f, 0x55,
+    ],
+    // level 6
+    [
+        0x2d, 0xee, 0x93, 0xc5, 0xa6, 0x66, 0x45, 0x96, 0x46, 0xea, 0x7d, 0x22, 0xcc, 0xa9, 0xe1, 0xbc,
+        0xfe, 0xd7, 0x1e, 0x69, 0x51, 0xb9, 0x53, 0x61, 0x1d, 0x11, 0xdd, 0xa3, 0x2e, 0xa0, 0x9d, 0x78,
+    ],
+    // level 7
+    [
+        0x07, 0x82, 0x95, 0xe5, 0xa2, 0x2b, 0x84, 0xe9, 0x82, 0xcf, 0x60, 0x1e, 0xb6, 0x39, 0x59, 0x7b,
+        0x8b, 0x05, 0x15, 0xa8, 0x8c, 0xb5, 0xac, 0x7f, 0xa8, 0xa4, 0xaa, 0xbe, 0x3c, 0x87, 0x34, 0x9d,
+    ],
+    // level 8
+    [
+        0x2f, 0xa5, 0xe5, 0xf1, 0x8f, 0x60, 0x27, 0xa6, 0x50, 0x1b, 0xec, 0x86, 0x45, 0x64, 0x47, 0x2a,
+        0x61, 0x6b, 0x2e, 0x27, 0x4a, 0x41, 0x21, 0x1a, 0x44, 0x4c, 0xbe, 0x3a, 0x99, 0xf3, 0xcc, 0x61,
+    ],
+    // level 9
+    [
+        0x0e, 0x88, 0x43, 0x76, 0xd0, 0xd8, 0xfd, 0x21, 0xec, 0xb7, 0x80, 0x38, 0x9e, 0x94, 0x1f, 0x66,
+        0xe4, 0x5e, 0x7a, 0xcc, 0xe3, 0xe2, 0x28, 0xab, 0x3e, 0x21, 0x56, 0xa6, 0x14, 0xfc, 0xd7, 0x47,
+    ],
+    // level 10
+    [
+        0x1b, 0x72, 0x01, 0xda, 0x72, 0x49, 0x4f, 0x1e, 0x28, 0x71, 0x7a, 0xd1, 0xa5, 0x2e, 0xb4, 0x69,
+        0xf9, 0x58, 0x92, 0xf9, 0x57, 0x71, 0x35, 0x33, 0xde, 0x61, 0x75, 0xe5, 0xda, 0x19, 0x0a, 0xf2,
+    ],
+    // level 11
+    [
+        0x1f, 0x8d, 0x88, 0x22, 0x72, 0x5e, 0x36, 0x38, 0x52, 0x00, 0xc0, 0xb2, 0x01, 0x24, 0x98, 0x19,
+        0xa6, 0xe6, 0xe1, 0xe4, 0x65, 0x08, 0x08, 0xb5, 0xbe, 0xbc, 0x6b, 0xfa, 0xce, 0x7d, 0x76, 0x36,
+    ],
+    // level 12
+    [
+        0x2c, 0x5d, 0x82, 0xf6, 0x6c, 0x91, 0x4b, 0xaf, 0xb9, 0x70, 0x15, 0x89, 0xba, 0x8c, 0xfc, 0xfb,
+        0x61, 0x62, 0xb0, 0xa1, 0x2a, 0xcf, 0x88, 0xa8, 0xd0, 0x87, 0x9a, 0x04, 0x71, 0xb5, 0xf8, 0x5a,
+    ],
+    // level 13
+    [
+        0x14, 0xc5, 0x41, 0x48, 0xa0, 0x94, 0x0b, 0xb8, 0x20, 0x95, 0x7f, 0x5a, 0xdf, 0x3f, 0xa1, 0x13,
+        0x4e, 0xf5, 0xc4, 0xaa, 0xa1, 0x13, 0xf4, 0x64, 0x64, 0x58, 0xf2, 0x70, 0xe0, 0xbf, 0xbf, 0xd0,
+    ],
+    // level 14
+    [
+        0x19, 0x0d, 0x33, 0xb1, 0x2f, 0x98, 0x6f, 0x96, 0x1e, 0x10, 0xc0, 0xee, 0x44, 0xd8, 0xb9, 0xaf,
+        0x11, 0xbe, 0x25, 0x58, 0x8c, 0xad, 0x89, 0xd4, 0x16, 0x11, 0x8e, 0x4b, 0xf4, 0xeb, 0xe8, 0x0c,
+    ],
+    // level 15
+    [
+        0x22, 0xf9, 0x8a, 0xa9, 0xce, 0x70, 0x41, 0x52, 0xac, 0x17, 0x35, 0x49, 0x14, 0xad, 0x73, 0xed,
+        0x11, 0x67, 0xae, 0x65, 0x96, 0xaf, 0x51, 0x0a, 0xa5, 0xb3, 0x64, 0x93, 0x25, 0xe0, 0x6c, 0x92,
+    ],
+    // level 16
+    [
+        0x2a, 0x7c, 0x7c, 0x9b, 0x6c, 0xe5, 0x88, 0x0b, 0x9f, 0x6f, 0x22, 0x8d, 0x72, 0xbf, 0x6a, 0x57,
+        0x5a, 0x52, 0x6f, 0x29, 0xc6, 0x6e, 0xcc, 0xee, 0xf8, 0xb7, 0x53, 0xd3, 0x8b, 0xba, 0x73, 0x23,
+    ],
+    // level 17
+    [
+        0x2e, 0x81, 0x86, 0xe5, 0x58, 0x69, 0x8e, 0xc1, 0xc6, 0x7a, 0xf9, 0xc1, 0x4d, 0x46, 0x3f, 0xfc,
+        0x47, 0x00, 0x43, 0xc9, 0xc2, 0x98, 0x8b, 0x95, 0x4d, 0x75, 0xdd, 0x64, 0x3f, 0x36, 0xb9, 0x92,
+    ],
+    // level 18
+    [
+        0x0f, 0x57, 0xc5, 0x57, 0x1e, 0x9a, 0x4e, 0xab, 0x49, 0xe2, 0xc8, 0xcf, 0x05, 0x0d, 0xae, 0x94,
+        0x8a, 0xef, 0x6e, 0xad, 0x64, 0x73, 0x92, 0x27, 0x35, 0x46, 0x24, 0x9d, 0x1c, 0x1f, 0xf1, 0x0f,
+    ],
+    // level 19
+    [
+        0x18, 0x30, 0xee, 0x67, 0xb5, 0xfb, 0x55, 0x4a, 0xd5, 0xf6, 0x3d, 0x43, 0x88, 0x80, 0x0e, 0x1c,
+        0xfe, 0x78, 0xe3, 0x10, 0x69, 0x7d, 0x46, 0xe4, 0x3c, 0x9c, 0xe3, 0x61, 0x34, 0xf7, 0x2c, 0xca,
+    ],
+    // level 20
+    [
+        0x21, 0x34, 0xe7, 0x6a, 0xc5, 0xd2, 0x1a, 0xab, 0x18, 0x6c, 0x2b, 0xe1, 0xdd, 0x8f, 0x84, 0xee,
+        0x88, 0x0a, 0x1e, 0x46, 0xea, 0xf7, 0x12, 0xf9, 0xd3, 0x71, 0xb6, 0xdf, 0x22, 0x19, 0x1f, 0x3e,
+    ],
+];
 
 impl MerkleTreeAccount {
-    pub fn initialize(&mut self, height: u8) -> Result<()> {
-        require!(height > 0, ShieldError::CapacityExceeded);
+    pub fn initialize(&mut self, height: u8, root_history_len: u32) -> Result<()> {
+        require!(height > 0 && height <= MAX_TREE_HEIGHT, ShieldError::CapacityExceeded);
+        require!(
+            root_history_len > 0 && root_history_len as usize <= MAX_ROOT_HISTORY,
+            ShieldError::CapacityExceeded
+        );
         self.height = height;
         self.current_index = 0;
-        self.filled_subtrees = default_zero_hashes(height);
-        self.cached_roots = vec![self.filled_subtrees[(height - 1) as usize]];
+        self.filled_subtrees[..height as usize].copy_from_slice(&POSEIDON_ZERO_HASHES[..height as usize]);
+        self.root_history_len = root_history_len;
+        self.root_head = 0;
+        self.root_count = 1;
+        self.roots[0] = POSEIDON_ZERO_HASHES[height as usize];
         Ok(())
     }
 
+    /// Insert a note commitment given as the little-endian instruction argument.
+    pub fn append_commitment(&mut self, commitment: &[u8; 32]) -> Result<[u8; 32]> {
+        self.append_leaf(canonical_field_from_le(commitment)?)
+    }
+
+    /// Insert a leaf given as a big-endian field element, the encoding of every tree node.
     pub fn append_leaf(&mut self, leaf: [u8; 32]) -> Result<[u8; 32]> {
         let capacity = 1u64 << self.height;
         require!((self.current_index as u64) < capacity, ShieldError::TreeFull);
 
-        let zero_hashes = default_zero_hashes(self.height);
         let mut idx = self.current_index;
         let mut current = leaf;
 
         for level in 0..self.height {
             let lvl = level as usize;
-            if idx % 2 == 0 {
+            if idx.is_multiple_of(2) {
                 self.filled_subtrees[lvl] = current;
-                current = hash_nodes(&current, &zero_hashes[lvl]);
+                current = hash_nodes(&current, &POSEIDON_ZERO_HASHES[lvl])?;
             } else {
                 let left = self.filled_subtrees[lvl];
-                current = hash_nodes(&left, &current);
+                current = hash_nodes(&left, &current)?;
             }
             idx /= 2;
         }
@@ -42,27 +169,165 @@ impl MerkleTreeAccount {
     }
 
     pub fn latest_root(&self) -> [u8; 32] {
-        *self.cached_roots.last().unwrap_or(&self.filled_subtrees[(self.height - 1) as usize])
+        self.roots[self.root_head as usize]
     }
 
     pub fn contains_root(&self, root: &[u8; 32]) -> bool {
-        self.cached_roots.iter().any(|r| r == root)
+        // Only the slots written since initialize; unused slots are zeroed, not roots.
+        (0..self.root_count).any(|offset| {
+            let slot = (self.root_head + self.root_history_len - offset) % self.root_history_len;
+            &self.roots[slot as usize] == root
+        })
+    }
+
+    /// Spends must prove membership against a root this tree actually produced.
+    pub fn require_known_root(&self, root: &[u8; 32]) -> Result<()> {
+        require!(self.contains_root(root), ShieldError::UnknownMerkleRoot);
+        Ok(())
     }
 
     fn push_root(&mut self, root: [u8; 32]) {
-        if self.cached_roots.len() >= MAX_ROOT_HISTORY {
-            self.cached_roots.remove(0);
+        self.root_head = (self.root_head + 1) % self.root_history_len;
+        self.roots[self.root_head as usize] = root;
+        self.root_count = (self.root_count + 1).min(self.root_history_len);
+    }
+}
+
+/// Read path for the keccak trees of the original devnet deployments, which
+/// `migrate_merkle_tree` checks and records before replacing them.
+#[cfg(feature = "legacy-keccak-tree")]
+impl LegacyMerkleTreeAccount {
+    pub fn latest_root(&self) -> Option<[u8; 32]> {
+        self.cached_roots.last().copied()
+    }
+
+    pub fn contains_root(&self, root: &[u8; 32]) -> bool {
+        self.cached_roots.contains(root)
+    }
+
+    /// Root over the first `current_index` leaves, rebuilt from the stored subtrees with keccak.
+    /// `None` for an empty or full tree, where the subtrees do not determine it.
+    pub fn frontier_root(&self) -> Option<[u8; 32]> {
+        let leaves = self.current_index as u64;
+        if leaves == 0 || leaves >= 1u64 << self.height || self.filled_subtrees.len() != self.height as usize {
+            return None;
+        }
+        let mut zero = [0u8; 32];
+        let mut node = zero;
+        for (level, filled) in self.filled_subtrees.iter().enumerate() {
+            node = if (leaves >> level) & 1 == 1 {
+                legacy_hash_nodes(filled, &node)
+            } else {
+                legacy_hash_nodes(&node, &zero)
+            };
+            zero = legacy_hash_nodes(&zero, &zero);
         }
-        self.cached_roots.push(root);
+        Some(node)
+    }
+
+    /// The account must decode to the tree its own roots describe before it is discarded.
+    pub fn require_consistent(&self) -> Result<()> {
+        if let Some(root) = self.frontier_root() {
+            require!(self.latest_root() == Some(root), ShieldError::LegacyTreeMismatch);
+        }
+        Ok(())
     }
 }
 
-pub fn default_zero_hashes(height: u8) -> Vec<[u8; 32]> {
-    let mut zeros = Vec::with_capacity(height as usize);
-    let mut current = [0u8; 32];
-    for _ in 0..height {
-        zeros.push(current);
-        current = hash_nodes(&current, &current);
+/// `AccountLoader::load` slices `size_of::<MerkleTreeAccount>()` bytes without a length
+/// check, so a tree still in the pre-zero-copy layout would panic there instead of failing.
+pub fn load_tree<'a>(tree: &'a AccountLoader<MerkleTreeAccount>) -> Result<Ref<'a, MerkleTreeAccount>> {
+    require_current_layout(tree.as_ref())?;
+    tree.load()
+}
+
+/// Mutable counterpart of `load_tree`.
+pub fn load_tree_mut<'a>(tree: &'a AccountLoader<MerkleTreeAccount>) -> Result<RefMut<'a, MerkleTreeAccount>> {
+    require_current_layout(tree.as_ref())?;
+    tree.load_mut()
+}
+
+fn require_current_layout(info: &AccountInfo) -> Result<()> {
+    require!(info.data_len() >= MerkleTreeAccount::LEN, ShieldError::MerkleTreeNeedsMigration);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_history_evicts_oldest_root() {
+        let mut tree: Box<MerkleTreeAccount> = Box::new(bytemuck::Zeroable::zeroed());
+        tree.initialize(MAX_TREE_HEIGHT, 4).unwrap();
+        let empty_root = tree.latest_root();
+        assert!(tree.contains_root(&empty_root));
+        assert!(!tree.contains_root(&[0u8; 32]));
+
+        for i in 1..=4u8 {
+            tree.push_root([i; 32]);
+        }
+        assert_eq!(tree.latest_root(), [4u8; 32]);
+        assert!(!tree.contains_root(&empty_root));
+        assert!((1..=4u8).all(|i| tree.contains_root(&[i; 32])));
+    }
+
+    #[test]
+    fn legacy_sized_tree_fails_to_load_instead_of_panicking() {
+        use anchor_lang::Discriminator;
+
+        // 8 + height + current_index + 14 filled subtrees + 32 cached roots
+        let legacy_len = 8 + 1 + 4 + (4 + 14 * 32) + (4 + 32 * 32);
+        assert_eq!(legacy_len, 1493);
+        let mut data = vec![0u8; legacy_len];
+        data[..8].copy_from_slice(&MerkleTreeAccount::DISCRIMINATOR);
+        let key = Pubkey::new_unique();
+        let mut lamports = 1_000_000;
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+
+        let tree = AccountLoader::<MerkleTreeAccount>::try_from(&info).unwrap();
+        let expected = error!(ShieldError::MerkleTreeNeedsMigration);
+        assert_eq!(load_tree(&tree).map(|_| ()).unwrap_err(), expected);
+        assert_eq!(load_tree_mut(&tree).map(|_| ()).unwrap_err(), expected);
+    }
+
+    #[cfg(feature = "legacy-keccak-tree")]
+    #[test]
+    fn legacy_frontier_reproduces_the_keccak_root() {
+        // The append of the original devnet program
+        fn legacy_append(tree: &mut LegacyMerkleTreeAccount, leaf: [u8; 32]) {
+            let mut zeros = vec![[0u8; 32]];
+            for level in 1..tree.height as usize {
+                zeros.push(legacy_hash_nodes(&zeros[level - 1], &zeros[level - 1]));
+            }
+            let (mut idx, mut current) = (tree.current_index, leaf);
+            for (filled, zero) in tree.filled_subtrees.iter_mut().zip(&zeros) {
+                if idx.is_multiple_of(2) {
+                    *filled = current;
+                    current = legacy_hash_nodes(&current, zero);
+                } else {
+                    current = legacy_hash_nodes(filled, &current);
+                }
+                idx /= 2;
+            }
+            tree.current_index += 1;
+            tree.cached_roots.push(current);
+        }
+
+        let mut tree = LegacyMerkleTreeAccount {
+            height: 4,
+            current_index: 0,
+            filled_subtrees: vec![[0u8; 32]; 4],
+            cached_roots: Vec::new(),
+        };
+        assert_eq!(tree.frontier_root(), None);
+        for i in 1..16u8 {
+            legacy_append(&mut tree, [i; 32]);
+            assert_eq!(tree.frontier_root(), tree.latest_root());
+            tree.require_consistent().unwrap();
+        }
+
+        tree.filled_subtrees[0] = [0xff; 32];
+        assert_eq!(tree.require_consistent().unwrap_err(), error!(ShieldError::LegacyTreeMismatch));
     }
-    zeros
 }
@@ -0,0 +1,120 @@
+use anchor_lang::{prelude::*, system_program};
+
+use crate::{errors::ShieldError, state::ShieldedPool, utils::isqrt};
+
+impl ShieldedPool {
+    /// Book a swap into the reserves: `pool_credit` of the input asset arrived in the
+    /// pool vaults and `output_amount` of the other asset leaves them.
+    pub fn settle_swap(&mut self, input_is_sol: bool, pool_credit: u64, output_amount: u64) -> Result<()> {
+        let (input_reserve, output_reserve) = if input_is_sol {
+            (&mut self.sol_reserve, &mut self.noc_reserve)
+        } else {
+            (&mut self.noc_reserve, &mut self.sol_reserve)
+        };
+        *input_reserve = input_reserve
+            .checked_add(pool_credit)
+            .ok_or(ShieldError::CapacityExceeded)?;
+        *output_reserve = output_reserve
+            .checked_sub(output_amount)
+            .ok_or(ShieldError::InvalidAmount)?;
+        Ok(())
+    }
+
+    /// Derive the reserves from the pool vault balances. Without `skim` the reserves take
+    /// the whole balances, donations included; with it they keep their value, capped at the
+    /// balances, and the returned (SOL, NOC) excess is left for the treasury.
+    pub fn sync(&mut self, sol_balance: u64, noc_balance: u64, skim: bool) -> (u64, u64) {
+        if skim {
+            self.sol_reserve = self.sol_reserve.min(sol_balance);
+            self.noc_reserve = self.noc_reserve.min(noc_balance);
+        } else {
+            self.sol_reserve = sol_balance;
+            self.noc_reserve = noc_balance;
+        }
+        self.lp_total_supply = isqrt((self.sol_reserve as u128) * (self.noc_reserve as u128)) as u64;
+        (sol_balance - self.sol_reserve, noc_balance - self.noc_reserve)
+    }
+
+    /// Check the pool vaults, after the instruction's transfers, still hold the reserves.
+    pub fn require_backed(&self, sol_balance: u64, noc_balance: u64) -> Result<()> {
+        require!(
+            sol_balance >= self.sol_reserve && noc_balance >= self.noc_reserve,
+            ShieldError::VaultInsolvent
+        );
+        Ok(())
+    }
+}
+
+/// Lamports of the pool SOL vault available to back reserves: all but its rent-exempt minimum.
+pub fn pool_sol_balance(pool_sol_vault: &AccountInfo) -> Result<u64> {
+    Ok(pool_sol_vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(0)))
+}
+
+/// Move `amount` lamports between system accounts. `signer_seeds` is empty when `from` signed the transaction.
+pub fn transfer_lamports<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Transfer {
+                from: from.clone(),
+                to: to.clone(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swaps_move_reserves_that_the_vaults_must_back() {
+        let mut pool = ShieldedPool {
+            admin: Pubkey::new_unique(),
+            sol_reserve: 1_000,
+            noc_reserve: 5_000,
+            lp_total_supply: 0,
+            swap_fee_bps: 30,
+            bump: 255,
+            enabled: true,
+        };
+        pool.settle_swap(true, 100, 450).unwrap();
+        assert_eq!((pool.sol_reserve, pool.noc_reserve), (1_100, 4_550));
+        pool.require_backed(1_100, 4_550).unwrap();
+        assert!(pool.require_backed(1_099, 4_550).is_err());
+
+        pool.settle_swap(false, 50, 10).unwrap();
+        assert_eq!((pool.sol_reserve, pool.noc_reserve), (1_090, 4_600));
+        assert!(pool.settle_swap(false, 1, 1_091).is_err());
+    }
+
+    #[test]
+    fn sync_takes_reserves_from_the_vault_balances() {
+        let mut pool = ShieldedPool {
+            admin: Pubkey::new_unique(),
+            sol_reserve: 1_000,
+            noc_reserve: 4_000,
+            lp_total_supply: 2_000,
+            swap_fee_bps: 30,
+            bump: 255,
+            enabled: true,
+        };
+        // Donations on top of the reserves are skimmed, shortfalls cap the reserves
+        assert_eq!(pool.sync(1_200, 3_600, true), (200, 0));
+        assert_eq!((pool.sol_reserve, pool.noc_reserve, pool.lp_total_supply), (1_000, 3_600, 1_897));
+
+        assert_eq!(pool.sync(1_200, 3_600, false), (0, 0));
+        assert_eq!((pool.sol_reserve, pool.noc_reserve), (1_200, 3_600));
+        pool.require_backed(1_200, 3_600).unwrap();
+    }
+}
@@ -0,0 +1,477 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::ShieldError, state::CircuitId, verifier::sub_assign_be};
+
+// BN254 scalar field modulus r = 21888242871839275222246405745257275088548364400416034343698204186575808495617
+pub const SCALAR_MODULUS_BE: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
+];
+
+/// Public signal layout of each circuit, in snarkjs order: circuit outputs first,
+/// then the public inputs in the order they are declared in the template.
+///
+/// Field-element instruction arguments (commitments, nullifiers) are little-endian,
+/// as sent by the client; public inputs are big-endian (EIP-196). `token_mint` fields
+/// are a `SupportedAsset::mint_field`, already big-endian.
+pub enum PublicInputLayout<'a> {
+    /// deposit.circom: [commitment, amount, tokenMint, expectedCommitment]
+    ///
+    /// `amount` is what the vault is credited, net of any Token-2022 transfer fee.
+    Deposit {
+        commitment: &'a [u8; 32],
+        amount: u64,
+        token_mint: &'a [u8; 32],
+    },
+    /// withdraw.circom:
+    /// [claimedReceiver, claimedAmount, merkleRoot, receiver, nullifier, relayer, relayerFee, tokenMint]
+    ///
+    /// `relayer` is the default pubkey and `relayer_fee` 0 when the user submits directly.
+    Withdraw {
+        merkle_root: &'a [u8; 32],
+        receiver: &'a Pubkey,
+        amount: u64,
+        nullifier: &'a [u8; 32],
+        relayer: &'a Pubkey,
+        relayer_fee: u64,
+        token_mint: &'a [u8; 32],
+    },
+    /// partial_withdraw.circom:
+    /// [claimedReceiver, claimedWithdrawAmount, changeCommitment, tokenMint, merkleRoot, withdrawAmount,
+    ///  nullifier, receiver, relayer, relayerFee]
+    PartialWithdraw {
+        merkle_root: &'a [u8; 32],
+        receiver: &'a Pubkey,
+        withdraw_amount: u64,
+        change_commitment: &'a [u8; 32],
+        nullifier: &'a [u8; 32],
+        relayer: &'a Pubkey,
+        relayer_fee: u64,
+        token_mint: &'a [u8; 32],
+    },
+    /// transfer.circom / transfer-multi.circom: [outCommitment1..N, merkleRoot, nullifier1..M]
+    Transfer {
+        merkle_root: &'a [u8; 32],
+        output_commitments: &'a [[u8; 32]],
+        nullifiers: &'a [[u8; 32]],
+    },
+    /// consolidate.circom: [outCommitment, merkleRoot, nullifiers[0..N]]
+    Consolidate {
+        merkle_root: &'a [u8; 32],
+        output_commitment: &'a [u8; 32],
+        nullifiers: &'a [[u8; 32]],
+    },
+    /// swap.circom:
+    /// [inputCommitment, outputCommitment, inputAmount, inputTokenMint, outputTokenMint, merkleRoot, nullifier, expectedOutAmount]
+    Swap {
+        merkle_root: &'a [u8; 32],
+        output_commitment: &'a [u8; 32],
+        input_amount: u64,
+        input_mint: &'a [u8; 32],
+        output_mint: &'a [u8; 32],
+        nullifier: &'a [u8; 32],
+        output_amount: u64,
+    },
+    /// swap_v2.circom:
+    /// [inputCommitment, outputCommitment, changeCommitment, publicSwapAmount, inputTokenMint,
+    ///  outputTokenMint, merkleRoot, swapAmount, expectedOutAmount, nullifier]
+    SwapV2 {
+        merkle_root: &'a [u8; 32],
+        output_commitment: &'a [u8; 32],
+        change_commitment: &'a [u8; 32],
+        swap_amount: u64,
+        input_mint: &'a [u8; 32],
+        output_mint: &'a [u8; 32],
+        output_amount: u64,
+        nullifier: &'a [u8; 32],
+    },
+}
+
+impl CircuitId {
+    /// Public signal count of the circuit's `PublicInputLayout`, which its verifying key must
+    /// match; `None` where it depends on the number of notes spent and created.
+    pub const fn public_input_count(self) -> Option<usize> {
+        match self {
+            CircuitId::Deposit => Some(4),
+            CircuitId::Withdraw => Some(8),
+            CircuitId::PartialWithdraw => Some(10),
+            CircuitId::Swap => Some(8),
+            CircuitId::SwapV2 => Some(10),
+            CircuitId::Transfer | CircuitId::Consolidate => None,
+        }
+    }
+}
+
+impl PublicInputLayout<'_> {
+    /// Expected public inputs; `None` marks a slot the instruction has no argument for.
+    pub fn expected(&self) -> Vec<Option<[u8; 32]>> {
+        match self {
+            Self::Deposit {
+                commitment,
+                amount,
+                token_mint,
+            } => {
+                let commitment = field_from_le(commitment);
+                vec![
+                    Some(commitment),
+                    Some(u64_to_field(*amount)),
+                    Some(**token_mint),
+                    Some(commitment),
+                ]
+            }
+            Self::Withdraw {
+                merkle_root,
+                receiver,
+                amount,
+                nullifier,
+                relayer,
+                relayer_fee,
+                token_mint,
+            } => {
+                let receiver = pubkey_to_field(receiver);
+                vec![
+                    Some(receiver),
+                    Some(u64_to_field(*amount)),
+                    Some(field_from_le(merkle_root)),
+                    Some(receiver),
+                    Some(field_from_le(nullifier)),
+                    Some(pubkey_to_field(relayer)),
+                    Some(u64_to_field(*relayer_fee)),
+                    Some(**token_mint),
+                ]
+            }
+            Self::PartialWithdraw {
+                merkle_root,
+                receiver,
+                withdraw_amount,
+                change_commitment,
+                nullifier,
+                relayer,
+                relayer_fee,
+                token_mint,
+            } => {
+                let receiver = pubkey_to_field(receiver);
+                let withdraw_amount = u64_to_field(*withdraw_amount);
+                vec![
+                    Some(receiver),
+                    Some(withdraw_amount),
+                    Some(field_from_le(change_commitment)),
+                    Some(**token_mint),
+                    Some(field_from_le(merkle_root)),
+                    Some(withdraw_amount),
+                    Some(field_from_le(nullifier)),
+                    Some(receiver),
+                    Some(pubkey_to_field(relayer)),
+                    Some(u64_to_field(*relayer_fee)),
+                ]
+            }
+            Self::Transfer {
+                merkle_root,
+                output_commitments,
+                nullifiers,
+            } => {
+                let mut out = Vec::with_capacity(output_commitments.len() + 1 + nullifiers.len());
+                out.extend(output_commitments.iter().map(|c| Some(field_from_le(c))));
+                out.push(Some(field_from_le(merkle_root)));
+                out.extend(nullifiers.iter().map(|n| Some(field_from_le(n))));
+                out
+            }
+            Self::Consolidate {
+                merkle_root,
+                output_commitment,
+                nullifiers,
+            } => {
+                let mut out = Vec::with_capacity(2 + nullifiers.len());
+                out.push(Some(field_from_le(output_commitment)));
+                out.push(Some(field_from_le(merkle_root)));
+                out.extend(nullifiers.iter().map(|n| Some(field_from_le(n))));
+                out
+            }
+            Self::Swap {
+                merkle_root,
+                output_commitment,
+                input_amount,
+                input_mint,
+                output_mint,
+                nullifier,
+                output_amount,
+            } => vec![
+                None,
+                Some(field_from_le(output_commitment)),
+                Some(u64_to_field(*input_amount)),
+                Some(**input_mint),
+                Some(**output_mint),
+                Some(field_from_le(merkle_root)),
+                Some(field_from_le(nullifier)),
+                Some(u64_to_field(*output_amount)),
+            ],
+            Self::SwapV2 {
+                merkle_root,
+                output_commitment,
+                change_commitment,
+                swap_amount,
+                input_mint,
+                output_mint,
+                output_amount,
+                nullifier,
+            } => {
+                let swap_amount = u64_to_field(*swap_amount);
+                vec![
+                    None,
+                    Some(field_from_le(output_commitment)),
+                    Some(field_from_le(change_commitment)),
+                    Some(swap_amount),
+                    Some(**input_mint),
+                    Some(**output_mint),
+                    Some(field_from_le(merkle_root)),
+                    Some(swap_amount),
+                    Some(u64_to_field(*output_amount)),
+                    Some(field_from_le(nullifier)),
+                ]
+            }
+        }
+    }
+
+    /// Reject the proof's public inputs unless every bound slot equals the instruction's value.
+    pub fn check(&self, public_inputs: &[[u8; 32]]) -> Result<()> {
+        let expected = self.expected();
+        require!(expected.len() == public_inputs.len(), ShieldError::PublicInputMismatch);
+        for (index, (want, got)) in expected.iter().zip(public_inputs.iter()).enumerate() {
+            if let Some(want) = want {
+                if want != got {
+                    msg!("Public input {} does not match instruction arguments", index);
+                    return err!(ShieldError::PublicInputMismatch);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Little-endian instruction argument to big-endian public input.
+pub fn field_from_le(value_le: &[u8; 32]) -> [u8; 32] {
+    let mut out = *value_le;
+    out.reverse();
+    out
+}
+
+/// Reject a big-endian value that is not strictly below r. Nothing is ever reduced:
+/// distinct byte strings must never map to the same scalar.
+pub fn require_canonical(value_be: &[u8; 32]) -> Result<()> {
+    require!(*value_be < SCALAR_MODULUS_BE, ShieldError::NonCanonicalFieldElement);
+    Ok(())
+}
+
+/// `field_from_le` for nullifier and commitment arguments, which must already be canonical.
+pub fn canonical_field_from_le(value_le: &[u8; 32]) -> Result<[u8; 32]> {
+    let value = field_from_le(value_le);
+    require_canonical(&value)?;
+    Ok(value)
+}
+
+pub fn u64_to_field(value: u64) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[24..].copy_from_slice(&value.to_be_bytes());
+    out
+}
+
+/// Matches the client's `pubkeyToField`: the key bytes read big-endian, reduced mod r.
+pub fn pubkey_to_field(key: &Pubkey) -> [u8; 32] {
+    let mut value = key.to_bytes();
+    while value >= SCALAR_MODULUS_BE {
+        sub_assign_be(&mut value, &SCALAR_MODULUS_BE);
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn withdraw_layout_rejects_swapped_amount() {
+        let receiver = Pubkey::new_unique();
+        let relayer = Pubkey::new_unique();
+        let nullifier = [7u8; 32];
+        let merkle_root = [3u8; 32];
+        let layout = PublicInputLayout::Withdraw {
+            merkle_root: &merkle_root,
+            receiver: &receiver,
+            amount: 1_000,
+            nullifier: &nullifier,
+            relayer: &relayer,
+            relayer_fee: 10,
+            token_mint: &[4u8; 32],
+        };
+        let mut inputs: Vec<[u8; 32]> = layout
+            .expected()
+            .into_iter()
+            .map(|slot| slot.unwrap_or([9u8; 32]))
+            .collect();
+        assert!(layout.check(&inputs).is_ok());
+
+        inputs[1] = u64_to_field(1_000_000);
+        assert!(layout.check(&inputs).is_err());
+    }
+
+    #[test]
+    fn withdraw_layout_binds_relayer_and_fee() {
+        let receiver = Pubkey::new_unique();
+        let relayer = Pubkey::new_unique();
+        let nullifier = [7u8; 32];
+        let merkle_root = [3u8; 32];
+        let layout = |relayer, relayer_fee| PublicInputLayout::PartialWithdraw {
+            merkle_root: &merkle_root,
+            receiver: &receiver,
+            withdraw_amount: 1_000,
+            change_commitment: &[5u8; 32],
+            nullifier: &nullifier,
+            relayer,
+            relayer_fee,
+            token_mint: &[4u8; 32],
+        };
+        let proven: Vec<[u8; 32]> = layout(&relayer, 10).expected().into_iter().map(Option::unwrap).collect();
+        assert!(layout(&relayer, 10).check(&proven).is_ok());
+        assert!(layout(&relayer, 11).check(&proven).is_err());
+        assert!(layout(&Pubkey::new_unique(), 10).check(&proven).is_err());
+    }
+
+    #[test]
+    fn withdraw_layout_rejects_a_note_of_another_mint() {
+        let receiver = Pubkey::new_unique();
+        let relayer = Pubkey::default();
+        let layout = |token_mint| PublicInputLayout::Withdraw {
+            merkle_root: &[3u8; 32],
+            receiver: &receiver,
+            amount: 1_000,
+            nullifier: &[7u8; 32],
+            relayer: &relayer,
+            relayer_fee: 0,
+            token_mint,
+        };
+        let noc_note: Vec<[u8; 32]> = layout(&[4u8; 32]).expected().into_iter().map(Option::unwrap).collect();
+        assert!(layout(&[4u8; 32]).check(&noc_note).is_ok());
+        assert!(layout(&u64_to_field(1)).check(&noc_note).is_err());
+    }
+
+    #[test]
+    fn canonical_check_rejects_scalar_modulus_and_above() {
+        let mut below = SCALAR_MODULUS_BE;
+        below[31] -= 1;
+        assert!(require_canonical(&below).is_ok());
+        assert!(require_canonical(&SCALAR_MODULUS_BE).is_err());
+        assert!(require_canonical(&[0xff; 32]).is_err());
+
+        // 2^256 - 1 read little-endian; would alias a small scalar if it were reduced
+        assert!(canonical_field_from_le(&[0xff; 32]).is_err());
+        assert!(canonical_field_from_le(&field_from_le(&below)).is_ok());
+    }
+
+    #[test]
+    fn pubkey_to_field_reduces_below_scalar_modulus() {
+        let key = Pubkey::new_from_array([0xff; 32]);
+        let field = pubkey_to_field(&key);
+        assert!(field < SCALAR_MODULUS_BE);
+    }
+
+    #[test]
+    fn fixed_layouts_have_their_circuit_public_input_count() {
+        let (key, field) = (Pubkey::new_unique(), [1u8; 32]);
+        let layouts = [
+            (
+                CircuitId::Deposit,
+                PublicInputLayout::Deposit {
+                    commitment: &field,
+                    amount: 1,
+                    token_mint: &field,
+                },
+            ),
+            (
+                CircuitId::Withdraw,
+                PublicInputLayout::Withdraw {
+                    merkle_root: &field,
+                    receiver: &key,
+                    amount: 1,
+                    nullifier: &field,
+                    relayer: &key,
+                    relayer_fee: 0,
+                    token_mint: &field,
+                },
+            ),
+            (
+                CircuitId::PartialWithdraw,
+                PublicInputLayout::PartialWithdraw {
+                    merkle_root: &field,
+                    receiver: &key,
+                    withdraw_amount: 1,
+                    change_commitment: &field,
+                    nullifier: &field,
+                    relayer: &key,
+                    relayer_fee: 0,
+                    token_mint: &field,
+                },
+            ),
+            (
+                CircuitId::Swap,
+                PublicInputLayout::Swap {
+                    merkle_root: &field,
+                    output_commitment: &field,
+                    input_amount: 1,
+                    input_mint: &field,
+                    output_mint: &field,
+                    nullifier: &field,
+                    output_amount: 1,
+                },
+            ),
+            (
+                CircuitId::SwapV2,
+                PublicInputLayout::SwapV2 {
+                    merkle_root: &field,
+                    output_commitment: &field,
+                    change_commitment: &field,
+                    swap_amount: 1,
+                    input_mint: &field,
+                    output_mint: &field,
+                    output_amount: 1,
+                    nullifier: &field,
+                },
+            ),
+        ];
+        for (circuit_id, layout) in layouts {
+            assert_eq!(Some(layout.expected().len()), circuit_id.public_input_count(), "{circuit_id:?}");
+        }
+    }
+
+    /// Keys in `zk/keys` still exported from circuits that predate the public `tokenMint`
+    /// and relayer inputs. `finalize_verifier` refuses them; regenerate them as in
+    /// `docs/WITNESS.md` and drop them from this list.
+    const AWAITING_CEREMONY: [&str; 3] = ["deposit", "withdraw", "partial_withdraw"];
+
+    #[test]
+    fn checked_in_verifying_keys_match_their_public_input_layouts() {
+        let keys = [
+            ("deposit", CircuitId::Deposit),
+            ("withdraw", CircuitId::Withdraw),
+            ("partial_withdraw", CircuitId::PartialWithdraw),
+            ("swap", CircuitId::Swap),
+            ("swap_v2", CircuitId::SwapV2),
+        ];
+        for (name, circuit_id) in keys {
+            let path = format!("{}/../../zk/keys/{name}.vkey.json", env!("CARGO_MANIFEST_DIR"));
+            let raw = std::fs::read_to_string(&path).expect("missing verifying key");
+            // Read only the first document; some exports have a template appended after it
+            let json: serde_json::Value = serde_json::Deserializer::from_str(&raw)
+                .into_iter()
+                .next()
+                .expect("empty verifying key")
+                .expect("invalid verifying key json");
+            let n_public = json["nPublic"].as_u64().expect("missing nPublic") as usize;
+            let expected = circuit_id.public_input_count().unwrap();
+            if AWAITING_CEREMONY.contains(&name) {
+                assert_ne!(n_public, expected, "{name}.vkey.json was regenerated; drop it from AWAITING_CEREMONY");
+            } else {
+                assert_eq!(n_public, expected, "{name}.vkey.json has {n_public} public inputs, its layout {expected}");
+            }
+        }
+    }
+}
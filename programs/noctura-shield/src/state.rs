@@ -1,9 +1,14 @@
 use anchor_lang::prelude::*;
 
-pub const MAX_TREE_HEIGHT: u8 = 14; // 16k leaves (reduced for account size constraints)
-pub const MAX_ROOT_HISTORY: usize = 32;
-pub const MAX_NULLIFIERS: usize = 256; // Keep at 256 for account compatibility
+use crate::errors::ShieldError;
+
+pub const MAX_TREE_HEIGHT: u8 = 20; // TREE_HEIGHT of every circuit in zk/circuits
+pub const MAX_ROOT_HISTORY: usize = 256;
+pub const MAX_NULLIFIERS: usize = 256; // Legacy set, superseded by NullifierRecord PDAs
 pub const MAX_VERIFIER_BYTES: usize = 4096;
+pub const MAX_ADMIN_SIGNERS: usize = 8;
+pub const MAX_PENDING_PROPOSALS: usize = 16;
+pub const MAX_FEE_OVERRIDES: usize = 16;
 
 #[account]
 pub struct GlobalState {
@@ -13,31 +18,196 @@ pub struct GlobalState {
     pub priority_fee_bps: u16,
     pub tree: Pubkey,
     pub nullifier_set: Pubkey,
-    pub verifier: Pubkey,
+    /// Active `VerifierAccount` version per `CircuitId`; 0 means none is active
+    pub verifier_versions: [u16; CircuitId::COUNT],
     pub bump: u8,
+    /// Key proposed by `propose_admin`; becomes admin once it calls `accept_admin`
+    pub pending_admin: Pubkey,
 }
 
 impl GlobalState {
-    pub const LEN: usize = 8 + (32 * 5) + 2 + 2 + 1;
+    pub const LEN: usize = 8 + (32 * 4) + 2 + 2 + (2 * CircuitId::COUNT) + 1 + 32;
+
+    /// Whether `verifier` is the version of `circuit_id` that spends must use.
+    pub fn is_active_verifier(&self, verifier: &VerifierAccount, circuit_id: CircuitId) -> bool {
+        verifier.circuit_id == circuit_id as u8
+            && verifier.is_finalized()
+            && verifier.version != 0
+            && verifier.version == self.verifier_versions[circuit_id as usize]
+    }
+}
+
+/// `GlobalState` as deployed before per-circuit verifier versions and the admin handover.
+/// Only `migrate_global_state` reads it, to rewrite the account in the current layout.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyGlobalState {
+    pub admin: Pubkey,
+    pub fee_collector: Pubkey,
+    pub shield_fee_bps: u16,
+    pub priority_fee_bps: u16,
+    pub tree: Pubkey,
+    pub nullifier_set: Pubkey,
+    pub verifier: Pubkey,
+    pub bump: u8,
+}
+
+impl LegacyGlobalState {
+    /// The old single verifier predates the `CircuitId` registry, so no circuit starts
+    /// with an active key; each is re-uploaded and activated through governance.
+    pub fn upgrade(self) -> GlobalState {
+        GlobalState {
+            admin: self.admin,
+            fee_collector: self.fee_collector,
+            shield_fee_bps: self.shield_fee_bps,
+            priority_fee_bps: self.priority_fee_bps,
+            tree: self.tree,
+            nullifier_set: self.nullifier_set,
+            verifier_versions: [0; CircuitId::COUNT],
+            bump: self.bump,
+            pending_admin: Pubkey::default(),
+        }
+    }
+}
+
+/// Optional M-of-N co-signing for super-admin actions.
+/// A `threshold` of 0 leaves the admin key acting alone.
+#[account]
+pub struct AdminConfig {
+    pub threshold: u8,
+    pub signers: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl AdminConfig {
+    pub const LEN: usize = 8 + 1 + 4 + (MAX_ADMIN_SIGNERS * 32) + 1;
+
+    /// Number of distinct configured signers among `keys`.
+    pub fn approvals<'a>(&self, keys: impl Iterator<Item = &'a Pubkey>) -> usize {
+        let mut seen: Vec<&Pubkey> = Vec::new();
+        for key in keys {
+            if self.signers.contains(key) && !seen.contains(&key) {
+                seen.push(key);
+            }
+        }
+        seen.len()
+    }
+}
+
+/// A change that only takes effect through the governance timelock.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum GovernanceAction {
+    /// Make `version` the active verifier of `circuit_id`; its key must still hash to `key_hash`
+    ActivateVerifier {
+        circuit_id: CircuitId,
+        version: u16,
+        key_hash: [u8; 32],
+    },
+    SetFee { shield_fee_bps: u16, priority_fee_bps: u16 },
+    SetDelay { delay_seconds: i64 },
+    /// Change the fee collector, which receives fee withdrawals and vault surplus sweeps
+    SetFeeCollector { fee_collector: Pubkey },
+    /// Replace the `FeeSchedule` rule of `operation`, or of `operation` for `mint` only.
+    /// A `None` rule with a mint removes that mint's override.
+    SetFeeRule {
+        operation: FeeOperation,
+        mint: Option<Pubkey>,
+        rule: Option<FeeRule>,
+    },
+}
+
+impl GovernanceAction {
+    pub const MAX_LEN: usize = 1 + 1 + 33 + 1 + FeeRule::LEN;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Proposal {
+    pub id: u64,
+    /// Unix timestamp from which the proposal can be executed
+    pub eta: i64,
+    pub action: GovernanceAction,
+}
+
+impl Proposal {
+    pub const MAX_LEN: usize = 8 + 8 + GovernanceAction::MAX_LEN;
 }
 
+/// Pending governance changes, each executable once its `eta` has passed.
 #[account]
+pub struct GovernanceQueue {
+    pub delay_seconds: i64,
+    pub next_proposal_id: u64,
+    pub proposals: Vec<Proposal>,
+    pub bump: u8,
+}
+
+impl GovernanceQueue {
+    pub const LEN: usize = 8 + 8 + 8 + 4 + (MAX_PENDING_PROPOSALS * Proposal::MAX_LEN) + 1;
+}
+
+/// Privileged capabilities, granted and revoked by the super-admin (`GlobalState::admin`).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// Upload and replace Groth16 verifying keys
+    VerifierAdmin,
+    /// Create, seed and rebalance the shielded AMM pool
+    PoolAdmin,
+    /// Change fees and the fee collector
+    FeeAdmin,
+    /// Move funds out of program vaults
+    Treasury,
+    /// Emergency and maintenance operations on spent-note state
+    Guardian,
+}
+
+impl Role {
+    pub const fn mask(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Roles held by one key, seeded by the holder's pubkey.
+#[account]
+pub struct RoleAccount {
+    pub holder: Pubkey,
+    /// Bitmask of `Role::mask` values
+    pub roles: u8,
+    pub bump: u8,
+}
+
+impl RoleAccount {
+    pub const LEN: usize = 8 + 32 + 1 + 1;
+
+    pub fn has(&self, role: Role) -> bool {
+        self.roles & role.mask() != 0
+    }
+}
+
+/// Zero-copy incremental Merkle tree. Recent roots live in a ring buffer of
+/// `root_history_len` slots; `root_head` is the slot holding the latest root.
+#[account(zero_copy)]
 pub struct MerkleTreeAccount {
-    pub height: u8,
+    pub filled_subtrees: [[u8; 32]; MAX_TREE_HEIGHT as usize],
+    pub roots: [[u8; 32]; MAX_ROOT_HISTORY],
     pub current_index: u32,
-    pub filled_subtrees: Vec<[u8; 32]>,
-    pub cached_roots: Vec<[u8; 32]>,
+    pub root_head: u32,
+    pub root_count: u32,
+    pub root_history_len: u32,
+    pub height: u8,
+    pub _padding: [u8; 3],
 }
 
 impl MerkleTreeAccount {
-    pub fn space(height: u8) -> usize {
-        let vec_overhead = 4; // Anchor stores Vec len as u32
-        8 // discriminator
-        + 1 // height
-        + 4 // current_index
-        + vec_overhead + (height as usize * 32)
-        + vec_overhead + (MAX_ROOT_HISTORY * 32)
-    }
+    pub const LEN: usize = 8 + std::mem::size_of::<MerkleTreeAccount>();
+}
+
+/// `MerkleTreeAccount` as deployed before the zero-copy layout: a Borsh keccak tree under the
+/// same seed and discriminator. Only `migrate_merkle_tree` reads it, to replace it in place.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyMerkleTreeAccount {
+    pub height: u8,
+    pub current_index: u32,
+    pub filled_subtrees: Vec<[u8; 32]>,
+    pub cached_roots: Vec<[u8; 32]>,
 }
 
 #[account]
@@ -51,19 +221,206 @@ impl NullifierSetAccount {
     }
 }
 
+/// One PDA per spent nullifier, seeded by the nullifier bytes.
+/// The account existing is what marks the note as spent.
 #[account]
+pub struct NullifierRecord {
+    pub nullifier: [u8; 32],
+}
+
+impl NullifierRecord {
+    pub const LEN: usize = 8 + 32;
+}
+
+/// Circuits with a Groth16 verifier; the discriminant is part of the verifier PDA seeds.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitId {
+    Deposit,
+    Withdraw,
+    Transfer,
+    PartialWithdraw,
+    Swap,
+    SwapV2,
+    Consolidate,
+}
+
+impl CircuitId {
+    pub const COUNT: usize = 7;
+    pub const ALL: [CircuitId; CircuitId::COUNT] = [
+        CircuitId::Deposit,
+        CircuitId::Withdraw,
+        CircuitId::Transfer,
+        CircuitId::PartialWithdraw,
+        CircuitId::Swap,
+        CircuitId::SwapV2,
+        CircuitId::Consolidate,
+    ];
+}
+
+/// One uploaded verifying key, seeded by `[b"verifier", circuit_id, version]`.
+/// Immutable once `finalized`; `key_hash` is the sha256 of the key as uploaded, before
+/// finalize rewrote it into the prepared layout. Zero-copy, so a proof reads the key in
+/// place instead of deserializing `MAX_VERIFIER_BYTES` on every spend.
+#[account(zero_copy)]
 pub struct VerifierAccount {
-    pub verifying_key: Vec<u8>,
+    pub key_hash: [u8; 32],
+    /// The first `key_len` bytes hold the uploaded key
+    pub verifying_key: [u8; MAX_VERIFIER_BYTES],
+    pub key_len: u32,
+    pub version: u16,
+    /// `CircuitId` discriminant
+    pub circuit_id: u8,
+    pub finalized: u8,
+    /// How many of beta, gamma and delta have passed `check_verifier_subgroup`
+    pub g2_checked: u8,
+    pub _padding: [u8; 3],
 }
 
 impl VerifierAccount {
-    pub const fn space() -> usize {
-        8 + 4 + MAX_VERIFIER_BYTES
+    pub const LEN: usize = 8 + std::mem::size_of::<VerifierAccount>();
+
+    pub fn circuit(&self) -> Result<CircuitId> {
+        CircuitId::ALL
+            .get(self.circuit_id as usize)
+            .copied()
+            .ok_or_else(|| error!(ShieldError::InvalidVerifierKey))
+    }
+
+    pub fn is_finalized(&self) -> bool {
+        self.finalized != 0
+    }
+
+    pub fn key(&self) -> &[u8] {
+        &self.verifying_key[..self.key_len as usize]
+    }
+
+    pub fn key_mut(&mut self) -> &mut [u8] {
+        &mut self.verifying_key[..self.key_len as usize]
+    }
+
+    pub fn append(&mut self, chunk: &[u8]) -> Result<()> {
+        let start = self.key_len as usize;
+        require!(start + chunk.len() <= MAX_VERIFIER_BYTES, ShieldError::CapacityExceeded);
+        self.verifying_key[start..start + chunk.len()].copy_from_slice(chunk);
+        self.key_len += chunk.len() as u32;
+        Ok(())
     }
 }
 
+/// Operations the `FeeSchedule` prices; the discriminant indexes `FeeSchedule::rules`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeOperation {
+    Deposit,
+    PriorityDeposit,
+    Withdraw,
+    /// Transfer amounts are private, so only `min_fee` applies, charged in lamports
+    Transfer,
+    Swap,
+}
+
+impl FeeOperation {
+    pub const COUNT: usize = 5;
+}
+
+/// `bps` of the amount, raised to `min_fee` and capped at `max_fee` (0 = no cap).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeRule {
+    pub bps: u16,
+    pub min_fee: u64,
+    pub max_fee: u64,
+}
+
+impl FeeRule {
+    pub const LEN: usize = 2 + 8 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MintFeeOverride {
+    pub mint: Pubkey,
+    pub operation: FeeOperation,
+    pub rule: FeeRule,
+}
+
+impl MintFeeOverride {
+    pub const LEN: usize = 32 + 1 + FeeRule::LEN;
+}
+
+/// Protocol fees for every operation, changed only through governance.
+/// Native SOL is keyed by the wrapped SOL mint.
+#[account]
+pub struct FeeSchedule {
+    pub rules: [FeeRule; FeeOperation::COUNT],
+    pub overrides: Vec<MintFeeOverride>,
+    pub bump: u8,
+}
+
+impl FeeSchedule {
+    pub const LEN: usize = 8 + (FeeOperation::COUNT * FeeRule::LEN) + 4 + (MAX_FEE_OVERRIDES * MintFeeOverride::LEN) + 1;
+}
+
+/// A mint that notes may hold, seeded by `[b"asset", mint]`. Native SOL is keyed by the wrapped SOL mint.
+#[account]
+pub struct SupportedAsset {
+    pub mint: Pubkey,
+    /// The note `tokenMint` value for this mint, big-endian; bound into every deposit, withdraw and swap proof
+    pub mint_field: [u8; 32],
+    pub decimals: u8,
+    /// Deposits and swaps stop while false; withdrawals never do
+    pub enabled: bool,
+    /// Largest single deposit; 0 = no limit
+    pub max_deposit: u64,
+    /// Limit on `total_deposited`; 0 = no limit
+    pub deposit_cap: u64,
+    /// Deposits less withdrawals
+    pub total_deposited: u64,
+    pub bump: u8,
+}
+
+impl SupportedAsset {
+    pub const LEN: usize = 8 + 32 + 32 + 1 + 1 + 8 + 8 + 8 + 1;
+}
+
+/// One PDA per registered `mint_field`, seeded by its bytes, so no two mints share an encoding.
+#[account]
+pub struct MintFieldRecord {
+    pub mint: Pubkey,
+}
+
+impl MintFieldRecord {
+    pub const LEN: usize = 8 + 32;
+}
+
+/// What a mint's vault owes, seeded by `[b"vault-ledger", mint]`. Native SOL is keyed by the
+/// wrapped SOL mint. The vault must always hold at least `liabilities()`.
+#[account]
+pub struct VaultLedger {
+    pub mint: Pubkey,
+    /// Value that entered notes: deposits and swap outputs, plus the opening balance
+    pub shielded_deposits: u128,
+    /// Value that left notes: withdrawals and swap inputs
+    pub shielded_withdrawals: u128,
+    /// Fees charged but still held in the vault
+    pub fees: u64,
+    pub bump: u8,
+}
+
+impl VaultLedger {
+    pub const LEN: usize = 8 + 32 + 16 + 16 + 8 + 1;
+}
+
+/// One transfer of a `shielded_transfer_batch`, with the arguments of `shielded_transfer`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TransferArgs {
+    pub input_nullifiers: Vec<[u8; 32]>,
+    pub output_commitments: Vec<[u8; 32]>,
+    pub merkle_root: [u8; 32],
+    pub proof: Vec<u8>,
+    pub public_inputs: Vec<[u8; 32]>,
+}
+
 /// Shielded Liquidity Pool State
 /// Implements constant-product AMM (x * y = k) entirely within the shielded system
+/// Reserves are held in the pool's own SOL and token vaults, apart from note deposits
 #[account]
 pub struct ShieldedPool {
     /// Admin who can manage pool
@@ -157,7 +514,7 @@ fn integer_sqrt(n: u128) -> u128 {
         return 0;
     }
     let mut x = n;
-    let mut y = (x + 1) / 2;
+    let mut y = x.div_ceil(2);
     while y < x {
         x = y;
         y = (x + n / x) / 2;
@@ -180,3 +537,92 @@ impl LPPosition {
     pub const LEN: usize = 8 + 32 + 8 + 32;
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn role_masks_are_independent() {
+        let mut account = RoleAccount {
+            holder: Pubkey::new_unique(),
+            roles: Role::VerifierAdmin.mask() | Role::Treasury.mask(),
+            bump: 255,
+        };
+        account.roles &= !Role::VerifierAdmin.mask();
+        assert!(!account.has(Role::VerifierAdmin));
+        assert!(account.has(Role::Treasury));
+        assert!(!account.has(Role::Guardian));
+    }
+
+    #[test]
+    fn only_the_active_version_of_a_circuit_verifies() {
+        let mut global = GlobalState {
+            admin: Pubkey::new_unique(),
+            fee_collector: Pubkey::new_unique(),
+            shield_fee_bps: 0,
+            priority_fee_bps: 0,
+            tree: Pubkey::new_unique(),
+            nullifier_set: Pubkey::new_unique(),
+            verifier_versions: [0; CircuitId::COUNT],
+            bump: 255,
+            pending_admin: Pubkey::default(),
+        };
+        let mut swap_v2: VerifierAccount = bytemuck::Zeroable::zeroed();
+        swap_v2.circuit_id = CircuitId::Swap as u8;
+        swap_v2.version = 2;
+        swap_v2.finalized = 1;
+        assert!(!global.is_active_verifier(&swap_v2, CircuitId::Swap));
+
+        global.verifier_versions[CircuitId::Swap as usize] = 2;
+        assert!(global.is_active_verifier(&swap_v2, CircuitId::Swap));
+        assert!(!global.is_active_verifier(&swap_v2, CircuitId::SwapV2));
+    }
+
+    #[test]
+    fn verifier_key_fills_up_to_capacity_and_no_further() {
+        let mut verifier: VerifierAccount = bytemuck::Zeroable::zeroed();
+        verifier.append(&[7u8; 1000]).unwrap();
+        verifier.append(&[9u8; MAX_VERIFIER_BYTES - 1000]).unwrap();
+        assert_eq!(verifier.key().len(), MAX_VERIFIER_BYTES);
+        assert_eq!((verifier.key()[999], verifier.key()[1000]), (7, 9));
+        assert_eq!(verifier.append(&[1]).unwrap_err(), error!(ShieldError::CapacityExceeded));
+
+        verifier.circuit_id = CircuitId::COUNT as u8;
+        assert_eq!(verifier.circuit().unwrap_err(), error!(ShieldError::InvalidVerifierKey));
+    }
+
+    #[test]
+    fn legacy_global_state_upgrades_without_an_active_verifier() {
+        let legacy = LegacyGlobalState {
+            admin: Pubkey::new_unique(),
+            fee_collector: Pubkey::new_unique(),
+            shield_fee_bps: 25,
+            priority_fee_bps: 50,
+            tree: Pubkey::new_unique(),
+            nullifier_set: Pubkey::new_unique(),
+            verifier: Pubkey::new_unique(),
+            bump: 254,
+        };
+        let bytes = legacy.try_to_vec().unwrap();
+        assert_eq!(bytes.len(), (32 * 5) + 2 + 2 + 1);
+
+        let global = LegacyGlobalState::deserialize(&mut &bytes[..]).unwrap().upgrade();
+        assert_eq!(global.admin, legacy.admin);
+        assert_eq!((global.shield_fee_bps, global.priority_fee_bps, global.bump), (25, 50, 254));
+        assert_eq!(global.verifier_versions, [0; CircuitId::COUNT]);
+        assert_eq!(global.pending_admin, Pubkey::default());
+    }
+
+    #[test]
+    fn admin_approvals_count_each_signer_once() {
+        let (a, b, outsider) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let config = AdminConfig {
+            threshold: 2,
+            signers: vec![a, b],
+            bump: 255,
+        };
+        assert_eq!(config.approvals([a, a, outsider].iter()), 1);
+        assert_eq!(config.approvals([a, outsider, b].iter()), 2);
+    }
+}
@@ -0,0 +1,200 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::ID as TOKEN_PROGRAM_ID,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    },
+    token_interface::Mint,
+};
+
+use crate::errors::ShieldError;
+
+/// Token-2022 mint extensions a vault can hold. Anything else (permanent delegates,
+/// non-transferable or confidential mints, closable mints, default-frozen accounts)
+/// could strand or drain shielded funds.
+pub const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 4] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::TransferHook,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+];
+
+/// Refuses Token-2022 mints carrying an extension outside `SUPPORTED_MINT_EXTENSIONS`.
+/// Legacy SPL Token mints have no extensions.
+pub fn require_supported_mint(mint: &AccountInfo) -> Result<()> {
+    if *mint.owner == TOKEN_PROGRAM_ID {
+        return Ok(());
+    }
+    check_mint_extensions(&mint.try_borrow_data()?)
+}
+
+fn check_mint_extensions(data: &[u8]) -> Result<()> {
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(data)?;
+    for extension in state.get_extension_types()? {
+        require!(
+            SUPPORTED_MINT_EXTENSIONS.contains(&extension),
+            ShieldError::UnsupportedMintExtension
+        );
+    }
+    Ok(())
+}
+
+/// Refuses mints with a transfer fee. Swap outputs leave the pool for a note of exactly the
+/// proven value, so nothing may be withheld on the way.
+pub fn require_no_transfer_fee(mint: &AccountInfo) -> Result<()> {
+    if *mint.owner == TOKEN_PROGRAM_ID {
+        return Ok(());
+    }
+    check_no_transfer_fee(&mint.try_borrow_data()?)
+}
+
+fn check_no_transfer_fee(data: &[u8]) -> Result<()> {
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(data)?;
+    require!(
+        state.get_extension::<TransferFeeConfig>().is_err(),
+        ShieldError::UnsupportedMintExtension
+    );
+    Ok(())
+}
+
+/// Tokens the mint's transfer-fee extension withholds from a transfer of `amount`
+/// in the current epoch; 0 for mints without one.
+fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    if *mint.owner == TOKEN_PROGRAM_ID {
+        return Ok(0);
+    }
+    epoch_transfer_fee(&mint.try_borrow_data()?, Clock::get()?.epoch, amount)
+}
+
+/// What reaches the destination when `amount` of `mint` is sent: a transfer-fee mint
+/// withholds part of every transfer.
+pub fn net_of_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    amount
+        .checked_sub(transfer_fee(mint, amount)?)
+        .ok_or(error!(ShieldError::FeeExceedsAmount))
+}
+
+fn epoch_transfer_fee(data: &[u8], epoch: u64, amount: u64) -> Result<u64> {
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(data)?;
+    let Ok(config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(0);
+    };
+    config
+        .calculate_epoch_fee(epoch, amount)
+        .ok_or(error!(ShieldError::InvalidAmount))
+}
+
+/// `transfer_checked` CPIs for one mint under either token program. `hook_accounts` are
+/// the instruction's remaining accounts, which must carry the extra accounts of a
+/// transfer-hook mint.
+pub struct CheckedTransfer<'a, 'info> {
+    pub token_program: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub decimals: u8,
+    pub hook_accounts: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> CheckedTransfer<'a, 'info> {
+    pub fn new(
+        token_program: AccountInfo<'info>,
+        mint: &InterfaceAccount<'info, Mint>,
+        hook_accounts: &'a [AccountInfo<'info>],
+    ) -> Self {
+        Self {
+            token_program,
+            mint: mint.to_account_info(),
+            decimals: mint.decimals,
+            hook_accounts,
+        }
+    }
+
+    /// Move `amount` from `from` to `to`. `signer_seeds` is empty when `authority` signed the transaction.
+    pub fn send(
+        &self,
+        from: AccountInfo<'info>,
+        to: AccountInfo<'info>,
+        authority: AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+        amount: u64,
+    ) -> Result<()> {
+        spl_token_2022::onchain::invoke_transfer_checked(
+            self.token_program.key,
+            from,
+            self.mint.clone(),
+            to,
+            authority,
+            self.hook_accounts,
+            amount,
+            self.decimals,
+            signer_seeds,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spl_token_2022::{
+        extension::{permanent_delegate::PermanentDelegate, transfer_fee::TransferFee, StateWithExtensionsMut},
+        state::Mint as MintState,
+    };
+
+    fn mint_data(extensions: &[ExtensionType], init: impl FnOnce(&mut StateWithExtensionsMut<MintState>)) -> Vec<u8> {
+        let len = ExtensionType::try_calculate_account_len::<MintState>(extensions).unwrap();
+        let mut data = vec![0u8; len];
+        let mut state = StateWithExtensionsMut::<MintState>::unpack_uninitialized(&mut data).unwrap();
+        init(&mut state);
+        state.base = MintState {
+            decimals: 6,
+            is_initialized: true,
+            ..MintState::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    }
+
+    #[test]
+    fn transfer_fee_follows_the_current_epoch_rate() {
+        let data = mint_data(&[ExtensionType::TransferFeeConfig], |state| {
+            let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+            config.older_transfer_fee = TransferFee {
+                epoch: 0.into(),
+                maximum_fee: 1_000.into(),
+                transfer_fee_basis_points: 100.into(),
+            };
+            config.newer_transfer_fee = TransferFee {
+                epoch: 10.into(),
+                maximum_fee: 1_000.into(),
+                transfer_fee_basis_points: 50.into(),
+            };
+        });
+        check_mint_extensions(&data).unwrap();
+        assert_eq!(epoch_transfer_fee(&data, 9, 10_000).unwrap(), 100);
+        assert_eq!(epoch_transfer_fee(&data, 10, 10_000).unwrap(), 50);
+        assert_eq!(epoch_transfer_fee(&data, 10, 10_000_000).unwrap(), 1_000);
+
+        let plain = mint_data(&[], |_| {});
+        assert_eq!(epoch_transfer_fee(&plain, 10, 10_000).unwrap(), 0);
+
+        // The pool can't hold a mint that charges one
+        assert_eq!(
+            check_no_transfer_fee(&data).unwrap_err(),
+            error!(ShieldError::UnsupportedMintExtension)
+        );
+        check_no_transfer_fee(&plain).unwrap();
+    }
+
+    #[test]
+    fn mints_with_a_permanent_delegate_are_refused() {
+        let data = mint_data(&[ExtensionType::PermanentDelegate], |state| {
+            state.init_extension::<PermanentDelegate>(true).unwrap();
+        });
+        assert_eq!(
+            check_mint_extensions(&data).unwrap_err(),
+            error!(ShieldError::UnsupportedMintExtension)
+        );
+    }
+}
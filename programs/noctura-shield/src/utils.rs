@@ -1,23 +1,142 @@
-use anchor_lang::prelude::*;
-use solana_program::keccak::hashv;
+use anchor_lang::{prelude::*, system_program};
 
 use crate::{
     errors::ShieldError,
-    state::{NullifierSetAccount, MAX_NULLIFIERS},
+    public_inputs::canonical_field_from_le,
+    state::{AdminConfig, NullifierRecord, NullifierSetAccount},
+    NULLIFIER_RECORD_SEED,
 };
 
-pub fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
-    hashv(&[left, right]).to_bytes()
+/// Poseidon(2) over big-endian BN254 field elements, matching `merkle.circom`.
+pub fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> Result<[u8; 32]> {
+    use solana_program::poseidon::{hashv, Endianness, Parameters};
+
+    hashv(Parameters::Bn254X5, Endianness::BigEndian, &[left, right])
+        .map(|hash| hash.to_bytes())
+        .map_err(|_| error!(ShieldError::NonCanonicalFieldElement))
+}
+
+/// Keccak node hash of the original devnet trees; these can never match circuit roots.
+#[cfg(feature = "legacy-keccak-tree")]
+pub fn legacy_hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    solana_program::keccak::hashv(&[left, right]).to_bytes()
+}
+
+/// Marks `nullifier` as spent by creating its `NullifierRecord` PDA.
+/// Fails if the record already exists or the nullifier is still in the legacy set.
+pub fn consume_nullifier<'info>(
+    legacy_set: &NullifierSetAccount,
+    record: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    nullifier: [u8; 32],
+) -> Result<()> {
+    canonical_field_from_le(&nullifier)?;
+    require!(!legacy_set.nullifiers.contains(&nullifier), ShieldError::NullifierUsed);
+    create_nullifier_record(record, payer, system_program, nullifier)
 }
 
-pub fn track_nullifier(set: &mut NullifierSetAccount, nullifier: [u8; 32]) -> Result<()> {
-    if set.nullifiers.iter().any(|item| item == &nullifier) {
+/// Moves one legacy-set nullifier into its `NullifierRecord` PDA. A record that already
+/// exists is left as is, but `record` must be that nullifier's PDA either way, or the
+/// legacy entry would be dropped without anything marking the note as spent.
+pub fn migrate_nullifier_record<'info>(
+    record: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    nullifier: [u8; 32],
+) -> Result<()> {
+    let (expected, _) = Pubkey::find_program_address(&[NULLIFIER_RECORD_SEED, &nullifier], &crate::ID);
+    require_keys_eq!(record.key(), expected, ShieldError::InvalidNullifierAccount);
+    if record.owner == &crate::ID {
+        return Ok(());
+    }
+    create_nullifier_record(record, payer, system_program, nullifier)
+}
+
+pub fn create_nullifier_record<'info>(
+    record: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    nullifier: [u8; 32],
+) -> Result<()> {
+    let (expected, bump) = Pubkey::find_program_address(&[NULLIFIER_RECORD_SEED, &nullifier], &crate::ID);
+    require_keys_eq!(record.key(), expected, ShieldError::InvalidNullifierAccount);
+    if record.owner == &crate::ID {
         return err!(ShieldError::NullifierUsed);
     }
-    if set.nullifiers.len() >= MAX_NULLIFIERS {
-        return err!(ShieldError::CapacityExceeded);
+
+    let space = NullifierRecord::LEN;
+    let rent_lamports = Rent::get()?.minimum_balance(space);
+    let signer: &[&[&[u8]]] = &[&[NULLIFIER_RECORD_SEED, &nullifier, &[bump]]];
+    let current_lamports = record.lamports();
+
+    if current_lamports == 0 {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: record.clone(),
+                },
+                signer,
+            ),
+            rent_lamports,
+            space as u64,
+            &crate::ID,
+        )?;
+    } else {
+        // Someone pre-funded the address; top it up and claim it instead of failing.
+        let top_up = rent_lamports.saturating_sub(current_lamports);
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    system_program::Transfer {
+                        from: payer.clone(),
+                        to: record.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::Allocate {
+                    account_to_allocate: record.clone(),
+                },
+                signer,
+            ),
+            space as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::Assign {
+                    account_to_assign: record.clone(),
+                },
+                signer,
+            ),
+            &crate::ID,
+        )?;
+    }
+
+    let mut data = record.try_borrow_mut_data()?;
+    NullifierRecord { nullifier }.try_serialize(&mut &mut data[..])?;
+    Ok(())
+}
+
+/// Enforces the `AdminConfig` co-signing threshold on a super-admin action.
+/// Co-signers are passed as signer remaining accounts; the admin counts if it is listed.
+pub fn require_admin_approval(config: &AdminConfig, admin: &Pubkey, co_signers: &[AccountInfo]) -> Result<()> {
+    if config.threshold == 0 {
+        return Ok(());
     }
-    set.nullifiers.push(nullifier);
+    let keys = std::iter::once(admin).chain(co_signers.iter().filter(|info| info.is_signer).map(|info| info.key));
+    require!(
+        config.approvals(keys) >= config.threshold as usize,
+        ShieldError::InsufficientApprovals
+    );
     Ok(())
 }
 
@@ -27,10 +146,47 @@ pub fn isqrt(n: u128) -> u128 {
         return 0;
     }
     let mut x = n;
-    let mut y = (x + 1) / 2;
+    let mut y = x.div_ceil(2);
     while y < x {
         x = y;
         y = (x + n / x) / 2;
     }
     x
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migration_rejects_a_program_account_that_is_not_the_nullifier_pda() {
+        // e.g. the GlobalState or tree PDA passed in place of the record
+        let (key, payer_key, system_key) = (Pubkey::new_unique(), Pubkey::new_unique(), system_program::ID);
+        let (mut lamports, mut payer_lamports, mut system_lamports) = (1_000_000, 1_000_000, 1);
+        let (mut data, mut payer_data, mut system_data) = (vec![0u8; 64], Vec::new(), Vec::new());
+        let record = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+        let payer = AccountInfo::new(
+            &payer_key,
+            true,
+            true,
+            &mut payer_lamports,
+            &mut payer_data,
+            &system_key,
+            false,
+            0,
+        );
+        let system = AccountInfo::new(
+            &system_key,
+            false,
+            false,
+            &mut system_lamports,
+            &mut system_data,
+            &system_key,
+            true,
+            0,
+        );
+
+        let err = migrate_nullifier_record(&record, &payer, &system, [7u8; 32]).unwrap_err();
+        assert_eq!(err, error!(ShieldError::InvalidNullifierAccount));
+    }
+}
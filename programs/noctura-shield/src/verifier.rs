@@ -1,10 +1,16 @@
-use core::cmp::Ordering;
 
 use anchor_lang::prelude::*;
 use solana_program::{
-    alt_bn128::prelude::*,
-    log::sol_log_data,
+    alt_bn128::{
+        compression::prelude::{
+            alt_bn128_g1_decompress, alt_bn128_g2_decompress, G1_COMPRESSED, G2_COMPRESSED,
+        },
+        prelude::*,
+    },
+    hash::hashv,
 };
+#[cfg(feature = "pairing-logs")]
+use solana_program::log::sol_log_data;
 
 #[cfg(test)]
 mod pairing_debug {
@@ -148,7 +154,12 @@ mod ic_accumulator_debug {
 #[cfg(test)]
 use ic_accumulator_debug::{log_ic_term, recorded_ic_terms};
 
-use crate::{errors::ShieldError, state::VerifierAccount};
+use crate::{
+    bn254::{check_g1, check_g2, require_g2_subgroup},
+    errors::ShieldError,
+    public_inputs::{require_canonical, PublicInputLayout},
+    state::{CircuitId, VerifierAccount},
+};
 
 const G1_BYTES: usize = 64;
 const G2_BYTES: usize = 128;
@@ -174,6 +185,57 @@ pub struct PackedVerifierKey {
     pub ic: Vec<[u8; G1_BYTES]>,
 }
 
+/// Zero-copy view of a finalized key: the `PackedVerifierKey` Borsh layout, with beta,
+/// gamma and delta negated by [`prepare_verifier_key`]. Verification reads points straight
+/// out of the account data instead of deserializing and negating on every proof.
+pub struct PreparedVerifierKey<'a> {
+    bytes: &'a [u8],
+}
+
+const ALPHA_OFFSET: usize = 0;
+const NEG_BETA_OFFSET: usize = ALPHA_OFFSET + G1_BYTES;
+const NEG_GAMMA_OFFSET: usize = NEG_BETA_OFFSET + G2_BYTES;
+const NEG_DELTA_OFFSET: usize = NEG_GAMMA_OFFSET + G2_BYTES;
+const IC_LEN_OFFSET: usize = NEG_DELTA_OFFSET + G2_BYTES;
+const IC_OFFSET: usize = IC_LEN_OFFSET + 4;
+
+impl<'a> PreparedVerifierKey<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self> {
+        require!(bytes.len() > IC_OFFSET, ShieldError::InvalidVerifierKey);
+        let ic_len = u32::from_le_bytes(bytes[IC_LEN_OFFSET..IC_OFFSET].try_into().unwrap()) as usize;
+        require!(
+            ic_len > 0 && bytes.len() == IC_OFFSET + ic_len * G1_BYTES,
+            ShieldError::InvalidVerifierKey
+        );
+        Ok(Self { bytes })
+    }
+
+    fn alpha_g1(&self) -> &'a [u8; G1_BYTES] {
+        self.bytes[ALPHA_OFFSET..NEG_BETA_OFFSET].try_into().unwrap()
+    }
+
+    fn neg_beta_g2(&self) -> &'a [u8; G2_BYTES] {
+        self.bytes[NEG_BETA_OFFSET..NEG_GAMMA_OFFSET].try_into().unwrap()
+    }
+
+    fn neg_gamma_g2(&self) -> &'a [u8; G2_BYTES] {
+        self.bytes[NEG_GAMMA_OFFSET..NEG_DELTA_OFFSET].try_into().unwrap()
+    }
+
+    fn neg_delta_g2(&self) -> &'a [u8; G2_BYTES] {
+        self.bytes[NEG_DELTA_OFFSET..IC_LEN_OFFSET].try_into().unwrap()
+    }
+
+    fn ic_len(&self) -> usize {
+        (self.bytes.len() - IC_OFFSET) / G1_BYTES
+    }
+
+    fn ic(&self, index: usize) -> &'a [u8; G1_BYTES] {
+        let start = IC_OFFSET + index * G1_BYTES;
+        self.bytes[start..start + G1_BYTES].try_into().unwrap()
+    }
+}
+
 struct Groth16Proof {
     a: [u8; G1_BYTES],
     b: [u8; G2_BYTES],
@@ -184,51 +246,38 @@ pub fn verify_groth16(
     verifier: &VerifierAccount,
     proof_bytes: &[u8],
     public_inputs: &[[u8; 32]],
+    layout: &PublicInputLayout,
 ) -> Result<()> {
-    require!(!verifier.verifying_key.is_empty(), ShieldError::VerifierMissing);
-
-    let key = load_verifier_key(&verifier.verifying_key)?;
-    require!(!key.ic.is_empty(), ShieldError::InvalidVerifierKey);
-    #[cfg(test)]
-    println!(
-        "verifier ic entries: {} expected: {}",
-        key.ic.len(),
-        public_inputs.len() + 1
-    );
-    require!(key.ic.len() == public_inputs.len() + 1, ShieldError::InvalidProof);
+    layout.check(public_inputs)?;
+    verify_proof(verifier, proof_bytes, public_inputs)
+}
 
-    let proof = Groth16Proof::from_bytes(proof_bytes)?;
-    log_public_inputs(public_inputs);
-    let scalars = normalize_public_inputs(public_inputs);
-    log_normalized_scalars(&scalars);
-    let vk_x = accumulate_ic(&key.ic, &scalars)?;
-    log_vk_accumulator(&vk_x);
+/// The Groth16 check alone; callers must already have bound `public_inputs` to the instruction.
+fn verify_proof(verifier: &VerifierAccount, proof_bytes: &[u8], public_inputs: &[[u8; 32]]) -> Result<()> {
+    let key = load_prepared_key(verifier)?;
+    let (proof, vk_x) = prepare_proof(&key, proof_bytes, public_inputs)?;
 
     // Groth16 verification equation: e(A, B) = e(alpha, beta) * e(vk_x, gamma) * e(C, delta)
     // Rewritten as product = 1: e(A, B) * e(alpha, -beta) * e(vk_x, -gamma) * e(C, -delta) = 1
-    let beta_neg = negate_g2(&key.beta_g2);
-    let gamma_neg = negate_g2(&key.gamma_g2);
-    let delta_neg = negate_g2(&key.delta_g2);
-
     let mut pairing_input = Vec::with_capacity(PAIRING_TERM_BYTES * 4);
     push_pair(&mut pairing_input, &proof.a, &proof.b);
-    push_pair(&mut pairing_input, &vk_x, &gamma_neg);
-    push_pair(&mut pairing_input, &proof.c, &delta_neg);
-    push_pair(&mut pairing_input, &key.alpha_g1, &beta_neg);
+    push_pair(&mut pairing_input, &vk_x, key.neg_gamma_g2());
+    push_pair(&mut pairing_input, &proof.c, key.neg_delta_g2());
+    push_pair(&mut pairing_input, key.alpha_g1(), key.neg_beta_g2());
 
     #[cfg(test)]
     record_pairing_terms(vec![
         PairingTerm::new("A * B", &proof.a, &proof.b),
-        PairingTerm::new("vk_x * -gamma", &vk_x, &gamma_neg),
-        PairingTerm::new("C * -delta", &proof.c, &delta_neg),
-        PairingTerm::new("alpha * -beta", &key.alpha_g1, &beta_neg),
+        PairingTerm::new("vk_x * -gamma", &vk_x, key.neg_gamma_g2()),
+        PairingTerm::new("C * -delta", &proof.c, key.neg_delta_g2()),
+        PairingTerm::new("alpha * -beta", key.alpha_g1(), key.neg_beta_g2()),
     ]);
 
     log_pairing_terms(&[
         (&proof.a, &proof.b, "proof.a x proof.b"),
-        (&vk_x, &gamma_neg, "vk_x x -gamma"),
-        (&proof.c, &delta_neg, "proof.c x -delta"),
-        (&key.alpha_g1, &beta_neg, "alpha x -beta"),
+        (&vk_x, key.neg_gamma_g2(), "vk_x x -gamma"),
+        (&proof.c, key.neg_delta_g2(), "proof.c x -delta"),
+        (key.alpha_g1(), key.neg_beta_g2(), "alpha x -beta"),
     ]);
 
     let result = alt_bn128_pairing(&pairing_input).map_err(|_| ShieldError::InvalidProof)?;
@@ -236,34 +285,135 @@ pub fn verify_groth16(
     Ok(())
 }
 
-fn log_public_inputs(inputs: &[[u8; 32]]) {
-    if inputs.is_empty() {
-        return;
+/// One proof of a batch, with the instruction arguments it must be bound to.
+pub struct BatchProof<'a> {
+    pub proof: &'a [u8],
+    pub public_inputs: &'a [[u8; 32]],
+    pub layout: PublicInputLayout<'a>,
+}
+
+/// Verify several proofs against one key with a random linear combination:
+///
+/// prod_i e(r_i A_i, B_i) * e(sum r_i vk_x_i, -gamma) * e(sum r_i C_i, -delta) * e((sum r_i) alpha, -beta) = 1
+///
+/// That is k + 3 pairings instead of 4k. The 128-bit weights r_i are a hash of the key and
+/// every proof and input, so they are fixed only after the prover has committed to all of them.
+pub fn verify_groth16_batch(verifier: &VerifierAccount, batch: &[BatchProof]) -> Result<()> {
+    let mut proofs = Vec::with_capacity(batch.len());
+    for entry in batch {
+        entry.layout.check(entry.public_inputs)?;
+        proofs.push((entry.proof, entry.public_inputs));
     }
-    if let Some(first) = inputs.get(0) {
-        sol_log_data(&[b"shield.public_inputs[0]", first.as_ref()]);
+    verify_proof_batch(verifier, &proofs)
+}
+
+/// `verify_groth16_batch` after each entry's inputs have been bound to the instruction.
+fn verify_proof_batch(verifier: &VerifierAccount, batch: &[(&[u8], &[[u8; 32]])]) -> Result<()> {
+    require!(!batch.is_empty(), ShieldError::InvalidProof);
+    if let [(proof, public_inputs)] = batch {
+        return verify_proof(verifier, proof, public_inputs);
     }
-    if let Some(second) = inputs.get(1) {
-        sol_log_data(&[b"shield.public_inputs[1]", second.as_ref()]);
+    let key = load_prepared_key(verifier)?;
+
+    let transcript = batch_transcript(verifier, batch);
+    let mut pairing_input = Vec::with_capacity(PAIRING_TERM_BYTES * (batch.len() + 3));
+    let mut vk_x_sum: Option<[u8; G1_BYTES]> = None;
+    let mut c_sum: Option<[u8; G1_BYTES]> = None;
+    let mut weight_sum = [0u8; 32];
+    for (index, (proof_bytes, public_inputs)) in batch.iter().enumerate() {
+        let (proof, vk_x) = prepare_proof(&key, proof_bytes, public_inputs)?;
+        let weight = batch_weight(&transcript, index);
+        push_pair(&mut pairing_input, &g1_scalar_mul(&proof.a, &weight)?, &proof.b);
+        vk_x_sum = Some(g1_accumulate(vk_x_sum, &g1_scalar_mul(&vk_x, &weight)?)?);
+        c_sum = Some(g1_accumulate(c_sum, &g1_scalar_mul(&proof.c, &weight)?)?);
+        add_assign_be(&mut weight_sum, &weight);
+    }
+    let alpha_term = g1_scalar_mul(key.alpha_g1(), &weight_sum)?;
+    // The loop ran at least twice, so both sums are set
+    push_pair(&mut pairing_input, &vk_x_sum.unwrap(), key.neg_gamma_g2());
+    push_pair(&mut pairing_input, &c_sum.unwrap(), key.neg_delta_g2());
+    push_pair(&mut pairing_input, &alpha_term, key.neg_beta_g2());
+
+    let result = alt_bn128_pairing(&pairing_input).map_err(|_| ShieldError::InvalidProof)?;
+    require!(result.as_slice() == PAIRING_SUCCESS, ShieldError::InvalidProof);
+    Ok(())
+}
+
+/// Checks shared by single and batched verification: canonical inputs, a well-formed
+/// proof, and the input accumulator vk_x.
+fn prepare_proof(
+    key: &PreparedVerifierKey,
+    proof_bytes: &[u8],
+    public_inputs: &[[u8; 32]],
+) -> Result<(Groth16Proof, [u8; G1_BYTES])> {
+    // Inputs >= r would alias a smaller scalar in the IC sum; reject them rather than reduce.
+    for input in public_inputs {
+        require_canonical(input)?;
     }
+    #[cfg(test)]
+    println!(
+        "verifier ic entries: {} expected: {}",
+        key.ic_len(),
+        public_inputs.len() + 1
+    );
+    require!(key.ic_len() == public_inputs.len() + 1, ShieldError::InvalidProof);
+
+    let proof = Groth16Proof::from_bytes(proof_bytes)?;
+    log_public_inputs(public_inputs);
+    let vk_x = accumulate_ic(key, public_inputs)?;
+    log_vk_accumulator(&vk_x);
+    Ok((proof, vk_x))
 }
 
-fn log_normalized_scalars(inputs: &[[u8; 32]]) {
+fn batch_transcript(verifier: &VerifierAccount, batch: &[(&[u8], &[[u8; 32]])]) -> [u8; 32] {
+    let mut parts: Vec<&[u8]> = vec![b"noctura.groth16.batch", verifier.key()];
+    for (proof, public_inputs) in batch {
+        parts.push(proof);
+        parts.extend(public_inputs.iter().map(|input| input.as_slice()));
+    }
+    hashv(&parts).to_bytes()
+}
+
+/// A nonzero 128-bit weight, big-endian in a 32-byte scalar.
+fn batch_weight(transcript: &[u8; 32], index: usize) -> [u8; 32] {
+    let digest = hashv(&[transcript, &(index as u64).to_le_bytes()]).to_bytes();
+    let mut weight = [0u8; 32];
+    weight[16..].copy_from_slice(&digest[..16]);
+    weight[31] |= 1;
+    weight
+}
+
+fn g1_accumulate(acc: Option<[u8; G1_BYTES]>, point: &[u8; G1_BYTES]) -> Result<[u8; G1_BYTES]> {
+    match acc {
+        Some(acc) => g1_add(&acc, point),
+        None => Ok(*point),
+    }
+}
+
+#[cfg(feature = "pairing-logs")]
+fn log_public_inputs(inputs: &[[u8; 32]]) {
     if inputs.is_empty() {
         return;
     }
-    if let Some(first) = inputs.get(0) {
-        sol_log_data(&[b"shield.scalars[0]", first.as_ref()]);
+    if let Some(first) = inputs.first() {
+        sol_log_data(&[b"shield.public_inputs[0]", first.as_ref()]);
     }
     if let Some(second) = inputs.get(1) {
-        sol_log_data(&[b"shield.scalars[1]", second.as_ref()]);
+        sol_log_data(&[b"shield.public_inputs[1]", second.as_ref()]);
     }
 }
 
+#[cfg(not(feature = "pairing-logs"))]
+fn log_public_inputs(_: &[[u8; 32]]) {}
+
+#[cfg(feature = "pairing-logs")]
 fn log_vk_accumulator(vk_x: &[u8; G1_BYTES]) {
     sol_log_data(&[b"shield.vk_x", vk_x.as_ref()]);
 }
 
+#[cfg(not(feature = "pairing-logs"))]
+fn log_vk_accumulator(_: &[u8; G1_BYTES]) {}
+
 #[cfg(feature = "pairing-logs")]
 fn log_pairing_terms(terms: &[(&[u8; G1_BYTES], &[u8; G2_BYTES], &'static str)]) {
     for (g1, g2, label) in terms {
@@ -274,33 +424,118 @@ fn log_pairing_terms(terms: &[(&[u8; G1_BYTES], &[u8; G2_BYTES], &'static str)])
 #[cfg(not(feature = "pairing-logs"))]
 fn log_pairing_terms(_: &[(&[u8; G1_BYTES], &[u8; G2_BYTES], &'static str)]) {}
 
-pub fn validate_verifier_key_blob(bytes: &[u8]) -> Result<()> {
+/// Offsets of beta, gamma and delta, the key points that need a G2 subgroup check.
+const KEY_G2_OFFSETS: [usize; 3] = [NEG_BETA_OFFSET, NEG_GAMMA_OFFSET, NEG_DELTA_OFFSET];
+pub const VERIFIER_G2_POINTS: u8 = KEY_G2_OFFSETS.len() as u8;
+
+/// Subgroup check of the `index`th G2 point of an uploaded, not yet prepared key. It costs
+/// a software scalar multiplication, so `check_verifier_subgroup` runs one point per call.
+pub fn check_verifier_g2_point(bytes: &[u8], index: usize) -> Result<()> {
+    let offset = *KEY_G2_OFFSETS.get(index).ok_or_else(|| error!(ShieldError::InvalidVerifierKey))?;
+    require!(bytes.len() >= offset + G2_BYTES, ShieldError::InvalidVerifierKey);
+    let point: &[u8; G2_BYTES] = bytes[offset..offset + G2_BYTES].try_into().unwrap();
+    require!(check_g2(point)?, ShieldError::InvalidVerifierKey);
+    require_g2_subgroup(point)
+}
+
+/// On-curve validation of an uploaded key whose G2 points have each passed
+/// [`check_verifier_g2_point`], then negate beta, gamma and delta in place, turning the
+/// Borsh `PackedVerifierKey` into a [`PreparedVerifierKey`]. Run once, when the key is
+/// finalized.
+pub fn prepare_verifier_key(bytes: &mut [u8]) -> Result<()> {
+    validate_verifier_key_blob(bytes)?;
+    for offset in KEY_G2_OFFSETS {
+        let point: &mut [u8; G2_BYTES] = (&mut bytes[offset..offset + G2_BYTES]).try_into().unwrap();
+        *point = negate_g2(point);
+    }
+    PreparedVerifierKey::new(bytes)?;
+    Ok(())
+}
+
+fn validate_verifier_key_blob(bytes: &[u8]) -> Result<()> {
     let key = load_verifier_key(bytes)?;
     require!(!key.ic.is_empty(), ShieldError::InvalidVerifierKey);
+    validate_verifier_key(&key)
+}
+
+fn validate_verifier_key(key: &PackedVerifierKey) -> Result<()> {
+    require!(check_g1(&key.alpha_g1)?, ShieldError::InvalidVerifierKey);
+    for point in [&key.beta_g2, &key.gamma_g2, &key.delta_g2] {
+        require!(check_g2(point)?, ShieldError::InvalidVerifierKey);
+    }
+    for point in &key.ic {
+        require!(check_g1(point)?, ShieldError::InvalidVerifierKey);
+    }
+    Ok(())
+}
+
+/// A key needs one IC point per public input of its circuit's layout, or no proof can ever
+/// verify against it; a key exported from an older circuit is refused at finalize instead.
+pub fn require_public_input_count(circuit_id: CircuitId, prepared_key: &[u8]) -> Result<()> {
+    if let Some(count) = circuit_id.public_input_count() {
+        require!(
+            PreparedVerifierKey::new(prepared_key)?.ic_len() == count + 1,
+            ShieldError::PublicInputCountMismatch
+        );
+    }
     Ok(())
 }
 
+/// Only finalized keys have been through `prepare_verifier_key`.
+fn load_prepared_key(verifier: &VerifierAccount) -> Result<PreparedVerifierKey<'_>> {
+    require!(verifier.is_finalized(), ShieldError::VerifierMissing);
+    PreparedVerifierKey::new(verifier.key())
+}
+
 fn load_verifier_key(bytes: &[u8]) -> Result<PackedVerifierKey> {
-    Ok(PackedVerifierKey::try_from_slice(bytes).map_err(|_| error!(ShieldError::InvalidVerifierKey))?)
+    PackedVerifierKey::try_from_slice(bytes).map_err(|_| error!(ShieldError::InvalidVerifierKey))
 }
 
+/// Version byte of a proof whose points are compressed as by `alt_bn128_g1_compress` /
+/// `alt_bn128_g2_compress`: `0x01 || A (32) || B (64) || C (32)`, 129 bytes.
+/// A bare 256-byte proof is the original uncompressed EIP-196 `A || B || C`.
+pub const PROOF_VERSION_COMPRESSED: u8 = 1;
+const PROOF_BYTES: usize = G1_BYTES + G2_BYTES + G1_BYTES;
+const COMPRESSED_PROOF_BYTES: usize = 1 + G1_COMPRESSED + G2_COMPRESSED + G1_COMPRESSED;
+
 impl Groth16Proof {
     fn from_bytes(bytes: &[u8]) -> Result<Self> {
-        const PROOF_BYTES: usize = G1_BYTES + G2_BYTES + G1_BYTES;
-        require!(bytes.len() == PROOF_BYTES, ShieldError::InvalidProof);
-        let mut a = [0u8; G1_BYTES];
-        let mut b = [0u8; G2_BYTES];
-        let mut c = [0u8; G1_BYTES];
-        a.copy_from_slice(&bytes[..G1_BYTES]);
-        b.copy_from_slice(&bytes[G1_BYTES..G1_BYTES + G2_BYTES]);
-        c.copy_from_slice(&bytes[G1_BYTES + G2_BYTES..]);
+        let (a, b, c) = match bytes.len() {
+            PROOF_BYTES => {
+                let mut a = [0u8; G1_BYTES];
+                let mut b = [0u8; G2_BYTES];
+                let mut c = [0u8; G1_BYTES];
+                a.copy_from_slice(&bytes[..G1_BYTES]);
+                b.copy_from_slice(&bytes[G1_BYTES..G1_BYTES + G2_BYTES]);
+                c.copy_from_slice(&bytes[G1_BYTES + G2_BYTES..]);
+                (a, b, c)
+            }
+            COMPRESSED_PROOF_BYTES if bytes[0] == PROOF_VERSION_COMPRESSED => {
+                let (a, rest) = bytes[1..].split_at(G1_COMPRESSED);
+                let (b, c) = rest.split_at(G2_COMPRESSED);
+                let a = alt_bn128_g1_decompress(a).map_err(|_| error!(ShieldError::InvalidProofEncoding))?;
+                let b = alt_bn128_g2_decompress(b).map_err(|_| error!(ShieldError::InvalidProofEncoding))?;
+                let c = alt_bn128_g1_decompress(c).map_err(|_| error!(ShieldError::InvalidProofEncoding))?;
+                (a, b, c)
+            }
+            _ => return err!(ShieldError::InvalidProofEncoding),
+        };
+        // B's subgroup membership is deliberately not checked: the pairing syscall decodes
+        // its G2 inputs with the subgroup check, so a B outside G2 fails the pairing and the
+        // proof is rejected as InvalidProof. Repeating it here would cost a G2 scalar
+        // multiplication per proof without rejecting anything more.
+        require!(check_g1(&a)?, ShieldError::ProofPointAtInfinity);
+        require!(check_g2(&b)?, ShieldError::ProofPointAtInfinity);
+        require!(check_g1(&c)?, ShieldError::ProofPointAtInfinity);
         Ok(Self { a, b, c })
     }
 }
 
-fn accumulate_ic(ic: &[[u8; G1_BYTES]], scalars: &[[u8; 32]]) -> Result<[u8; G1_BYTES]> {
-    let mut acc = ic[0];
-    for (_index, (scalar, point)) in scalars.iter().zip(ic.iter().skip(1)).enumerate() {
+// The index is only consumed by the test-build IC log
+#[cfg_attr(not(test), allow(clippy::unused_enumerate_index))]
+fn accumulate_ic(key: &PreparedVerifierKey, scalars: &[[u8; 32]]) -> Result<[u8; G1_BYTES]> {
+    let mut acc = *key.ic(0);
+    for (_index, (scalar, point)) in scalars.iter().zip((1..key.ic_len()).map(|i| key.ic(i))).enumerate() {
         #[cfg(test)]
         log_ic_term(_index, scalar, point);
         if is_zero(scalar) || is_zero(point) {
@@ -312,19 +547,6 @@ fn accumulate_ic(ic: &[[u8; G1_BYTES]], scalars: &[[u8; 32]]) -> Result<[u8; G1_
     Ok(acc)
 }
 
-fn normalize_public_inputs(inputs: &[[u8; 32]]) -> Vec<[u8; 32]> {
-    // Public inputs are already in big-endian format (EIP-196)
-    inputs.iter().map(|bytes| reduce_mod_order_be(bytes)).collect()
-}
-
-fn reduce_mod_order_be(input_be: &[u8; 32]) -> [u8; 32] {
-    let mut value = *input_be;
-    while cmp_be(&value, &FIELD_MODULUS_BE) != Ordering::Less {
-        sub_assign_be(&mut value, &FIELD_MODULUS_BE);
-    }
-    value
-}
-
 fn g1_add(p: &[u8; G1_BYTES], q: &[u8; G1_BYTES]) -> Result<[u8; G1_BYTES]> {
     let mut input = [0u8; G1_OP_INPUT_BYTES];
     // Solana alt_bn128_addition expects BE input (per EIP-196)
@@ -354,19 +576,23 @@ fn g1_scalar_mul(point: &[u8; G1_BYTES], scalar_be: &[u8; 32]) -> Result<[u8; G1
         println!("  point_y_be b64: {}", BASE64_STANDARD.encode(&input[32..64]));
         println!("  scalar_be b64:  {}", BASE64_STANDARD.encode(&input[64..96]));
     }
-    // Log the actual input to the syscall
-    sol_log_data(&[b"shield.scalar_mul.point_x", &input[0..32]]);
-    sol_log_data(&[b"shield.scalar_mul.point_y", &input[32..64]]);
-    sol_log_data(&[b"shield.scalar_mul.scalar", &input[64..96]]);
-    let raw = alt_bn128_multiplication(&input).map_err(|err| {
+    #[cfg(feature = "pairing-logs")]
+    {
+        sol_log_data(&[b"shield.scalar_mul.point_x", &input[0..32]]);
+        sol_log_data(&[b"shield.scalar_mul.point_y", &input[32..64]]);
+        sol_log_data(&[b"shield.scalar_mul.scalar", &input[64..96]]);
+    }
+    let raw = alt_bn128_multiplication(&input).map_err(|_err| {
         #[cfg(test)]
         {
-            println!("alt_bn128_multiplication failed: {err:?}");
+            println!("alt_bn128_multiplication failed: {_err:?}");
             log_scalar_mul_failure();
         }
-        // Log the error type
-        let err_code: u64 = err.clone().into();
-        sol_log_data(&[b"shield.scalar_mul.error", &err_code.to_le_bytes()]);
+        #[cfg(feature = "pairing-logs")]
+        {
+            let err_code: u64 = _err.clone().into();
+            sol_log_data(&[b"shield.scalar_mul.error", &err_code.to_le_bytes()]);
+        }
         error!(ShieldError::InvalidProof)
     })?;
     let arr = read_g1_be(raw.as_slice()).map_err(|_| error!(ShieldError::InvalidProof))?;
@@ -443,17 +669,9 @@ fn read_g1_be(src_be: &[u8]) -> Result<[u8; G1_BYTES]> {
     }
     let mut out = [0u8; G1_BYTES];
     out.copy_from_slice(src_be);
-    // Clear high bits if needed (shouldn't be necessary for valid points)
-    clear_high_bit(&mut out[..32].try_into().unwrap());
-    clear_high_bit(&mut out[32..].try_into().unwrap());
     Ok(out)
 }
 
-#[allow(dead_code)]
-fn clear_high_bit(coord: &mut [u8; 32]) {
-    coord[0] &= 0x7F;
-}
-
 #[allow(dead_code)]
 fn g1_to_le_bytes(point_be: &[u8; G1_BYTES]) -> [u8; G1_BYTES] {
     let mut out = [0u8; G1_BYTES];
@@ -476,16 +694,16 @@ fn g2_to_le_bytes(point_be: &[u8; G2_BYTES]) -> [u8; G2_BYTES] {
     out
 }
 
-fn cmp_be(a: &[u8; 32], b: &[u8; 32]) -> Ordering {
-    for i in 0..32 {
-        if a[i] != b[i] {
-            return a[i].cmp(&b[i]);
-        }
+fn add_assign_be(lhs: &mut [u8; 32], rhs: &[u8; 32]) {
+    let mut carry = 0u16;
+    for i in (0..32).rev() {
+        let sum = lhs[i] as u16 + rhs[i] as u16 + carry;
+        lhs[i] = sum as u8;
+        carry = sum >> 8;
     }
-    Ordering::Equal
 }
 
-fn sub_assign_be(lhs: &mut [u8; 32], rhs: &[u8]) {
+pub(crate) fn sub_assign_be(lhs: &mut [u8; 32], rhs: &[u8]) {
     let mut borrow = 0i16;
     for i in (0..32).rev() {
         let mut diff = lhs[i] as i16 - rhs[i] as i16 - borrow;
@@ -506,6 +724,10 @@ fn is_zero(bytes: &[u8]) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        public_inputs::{SCALAR_MODULUS_BE},
+        state::CircuitId,
+    };
     use anchor_lang::prelude::AnchorSerialize;
     use base64::{engine::general_purpose::STANDARD, Engine as _};
     use num_bigint::BigUint;
@@ -544,7 +766,7 @@ mod tests {
         out
     }
 
-    fn load_packed_key() -> VerifierAccount {
+    fn packed_deposit_key() -> Vec<u8> {
         let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         let vkey_path = manifest_dir.join("../../zk/keys/deposit.vkey.json");
         let raw = fs::read_to_string(vkey_path).expect("failed to read deposit.vkey.json");
@@ -558,10 +780,18 @@ mod tests {
             delta_g2: pack_g2(&json["vk_delta_2"]),
             ic,
         };
-        let verifying_key = packed
-            .try_to_vec()
-            .expect("failed to serialize verifier key");
-        VerifierAccount { verifying_key }
+        packed.try_to_vec().expect("failed to serialize verifier key")
+    }
+
+    fn load_packed_key() -> VerifierAccount {
+        let mut verifying_key = packed_deposit_key();
+        prepare_verifier_key(&mut verifying_key).expect("deposit key should prepare");
+        let mut verifier: VerifierAccount = bytemuck::Zeroable::zeroed();
+        verifier.circuit_id = CircuitId::Deposit as u8;
+        verifier.version = 1;
+        verifier.finalized = 1;
+        verifier.append(&verifying_key).expect("deposit key fits");
+        verifier
     }
 
     fn decode_base64_array<const N: usize>(value: &str) -> [u8; N] {
@@ -590,7 +820,8 @@ mod tests {
             .map(|value| decode_base64_array::<32>(value))
             .collect();
 
-        let result = verify_groth16(&verifier, &proof_bytes, &public_inputs);
+        // This fixture predates the tokenMint public input, so only the proof itself is checked
+        let result = verify_proof(&verifier, &proof_bytes, &public_inputs);
         if let Err(err) = result {
             dump_pairing_terms("deposit_vector_matches_js_verifier");
             dump_scalar_mul_records("deposit_vector_matches_js_verifier");
@@ -599,6 +830,110 @@ mod tests {
         }
     }
 
+    #[test]
+    fn deposit_vector_rejects_altered_public_inputs() {
+        let verifier = load_packed_key();
+        let proof_bytes = STANDARD
+            .decode("BPN0T9GKkKkLhOBagJaHdPuDRwmHQRW0Hmb+PmJu/5AfQIVrEf4hbitHR9dvJiTzQ613U2AqdlOA74uEj6Jo7xPxg7fPu2zg6hiGpMdlyAq8IuSfWNzsCR2YuXWP0ZWnEozvYBnRufnm8v+ws+Cgja0CM3LfLE81fS2CrgcYk6UqhxzsxXjeC6LwD/z7adLnfY6o3IMmKHpIXCYFQBwrVwc+8mKd7rpQ1utAEJQnPN8tbR3PWhDQKhhlsmVDmWjyI8bq1G9LWxVYnK8q1+4ENEfsKHEeGrXbC/kcUozWS6cWGsdpRlsJYGeGDS9IAVbpfj5kHFcNrILa/BiBIgUSbQ==")
+            .expect("invalid proof base64");
+        let input = decode_base64_array::<32>("BbUNpP4upFSSo5lZKpo1ddfTUWJfrvE5YcSVkisu2vM=");
+        let public_inputs = vec![input, input];
+
+        assert!(verify_proof(&verifier, &proof_bytes, &public_inputs).is_ok());
+
+        // A valid proof paired with a different commitment must not verify
+        let other_commitment = [1u8; 32];
+        let result = verify_proof(&verifier, &proof_bytes, &[other_commitment, other_commitment]);
+        assert_eq!(result.unwrap_err(), error!(ShieldError::InvalidProof));
+    }
+
+    #[test]
+    fn deposit_key_passes_point_validation_and_identity_proof_points_fail() {
+        validate_verifier_key_blob(&packed_deposit_key()).expect("deposit key should validate");
+
+        let mut proof_bytes = STANDARD
+            .decode("BPN0T9GKkKkLhOBagJaHdPuDRwmHQRW0Hmb+PmJu/5AfQIVrEf4hbitHR9dvJiTzQ613U2AqdlOA74uEj6Jo7xPxg7fPu2zg6hiGpMdlyAq8IuSfWNzsCR2YuXWP0ZWnEozvYBnRufnm8v+ws+Cgja0CM3LfLE81fS2CrgcYk6UqhxzsxXjeC6LwD/z7adLnfY6o3IMmKHpIXCYFQBwrVwc+8mKd7rpQ1utAEJQnPN8tbR3PWhDQKhhlsmVDmWjyI8bq1G9LWxVYnK8q1+4ENEfsKHEeGrXbC/kcUozWS6cWGsdpRlsJYGeGDS9IAVbpfj5kHFcNrILa/BiBIgUSbQ==")
+            .expect("invalid proof base64");
+        assert!(Groth16Proof::from_bytes(&proof_bytes).is_ok());
+
+        proof_bytes[G1_BYTES + G2_BYTES..].fill(0);
+        let err = Groth16Proof::from_bytes(&proof_bytes).err().expect("identity C must be rejected");
+        assert_eq!(err, error!(ShieldError::ProofPointAtInfinity));
+
+        proof_bytes[G1_BYTES - 1] ^= 1;
+        let err = Groth16Proof::from_bytes(&proof_bytes).err().expect("off-curve A must be rejected");
+        assert_eq!(err, error!(ShieldError::PointNotOnCurve));
+    }
+
+    #[test]
+    fn deposit_key_g2_points_pass_the_subgroup_check_one_at_a_time() {
+        let packed = packed_deposit_key();
+        for index in 0..VERIFIER_G2_POINTS as usize {
+            check_verifier_g2_point(&packed, index).expect("deposit G2 point should be in the subgroup");
+        }
+        let err = check_verifier_g2_point(&packed, VERIFIER_G2_POINTS as usize).unwrap_err();
+        assert_eq!(err, error!(ShieldError::InvalidVerifierKey));
+        let err = check_verifier_g2_point(&packed[..IC_LEN_OFFSET - 1], 2).unwrap_err();
+        assert_eq!(err, error!(ShieldError::InvalidVerifierKey));
+    }
+
+    #[test]
+    fn identity_ic_point_is_rejected() {
+        let mut packed = packed_deposit_key();
+        let last = packed.len() - G1_BYTES;
+        packed[last..].fill(0);
+        assert_eq!(validate_verifier_key_blob(&packed).unwrap_err(), error!(ShieldError::InvalidVerifierKey));
+        assert_eq!(prepare_verifier_key(&mut packed).unwrap_err(), error!(ShieldError::InvalidVerifierKey));
+    }
+
+    #[test]
+    fn finalized_key_stores_negated_g2_points() {
+        let packed = load_verifier_key(&packed_deposit_key()).expect("unable to decode verifier key");
+        let mut verifier = load_packed_key();
+        let key = PreparedVerifierKey::new(verifier.key()).expect("prepared key");
+        assert_eq!(key.alpha_g1(), &packed.alpha_g1);
+        assert_eq!(key.neg_beta_g2(), &negate_g2(&packed.beta_g2));
+        assert_eq!(key.neg_gamma_g2(), &negate_g2(&packed.gamma_g2));
+        assert_eq!(key.neg_delta_g2(), &negate_g2(&packed.delta_g2));
+        assert_eq!(key.ic_len(), packed.ic.len());
+        assert_eq!(key.ic(packed.ic.len() - 1), packed.ic.last().unwrap());
+
+        verifier.finalized = 0;
+        assert_eq!(load_prepared_key(&verifier).err(), Some(error!(ShieldError::VerifierMissing)));
+    }
+
+    #[test]
+    fn proof_encoding_is_chosen_by_length_and_version_byte() {
+        let invalid = || error!(ShieldError::InvalidProofEncoding);
+        assert_eq!(Groth16Proof::from_bytes(&[0u8; PROOF_BYTES - 1]).err(), Some(invalid()));
+
+        let mut compressed = [0u8; COMPRESSED_PROOF_BYTES];
+        compressed[0] = PROOF_VERSION_COMPRESSED + 1;
+        assert_eq!(Groth16Proof::from_bytes(&compressed).err(), Some(invalid()));
+
+        // The host build of the decompression syscalls always fails; on-chain this decodes
+        compressed[0] = PROOF_VERSION_COMPRESSED;
+        assert_eq!(Groth16Proof::from_bytes(&compressed).err(), Some(invalid()));
+    }
+
+    #[test]
+    fn batch_verifies_valid_proofs_and_rejects_a_tampered_one() {
+        let verifier = load_packed_key();
+        let proof_bytes = STANDARD
+            .decode("BPN0T9GKkKkLhOBagJaHdPuDRwmHQRW0Hmb+PmJu/5AfQIVrEf4hbitHR9dvJiTzQ613U2AqdlOA74uEj6Jo7xPxg7fPu2zg6hiGpMdlyAq8IuSfWNzsCR2YuXWP0ZWnEozvYBnRufnm8v+ws+Cgja0CM3LfLE81fS2CrgcYk6UqhxzsxXjeC6LwD/z7adLnfY6o3IMmKHpIXCYFQBwrVwc+8mKd7rpQ1utAEJQnPN8tbR3PWhDQKhhlsmVDmWjyI8bq1G9LWxVYnK8q1+4ENEfsKHEeGrXbC/kcUozWS6cWGsdpRlsJYGeGDS9IAVbpfj5kHFcNrILa/BiBIgUSbQ==")
+            .expect("invalid proof base64");
+        let input = decode_base64_array::<32>("BbUNpP4upFSSo5lZKpo1ddfTUWJfrvE5YcSVkisu2vM=");
+        let public_inputs = vec![input, input];
+        let entry = (proof_bytes.as_slice(), public_inputs.as_slice());
+        assert!(verify_proof_batch(&verifier, &[entry, entry, entry]).is_ok());
+
+        // Swap C for A: still a curve point, but the combined pairing check must fail
+        let mut tampered = proof_bytes.clone();
+        tampered.copy_within(..G1_BYTES, G1_BYTES + G2_BYTES);
+        let err = verify_proof_batch(&verifier, &[entry, (tampered.as_slice(), public_inputs.as_slice())]).unwrap_err();
+        assert_eq!(err, error!(ShieldError::InvalidProof));
+    }
+
     #[test]
     #[ignore = "Old proof with wrong encoding format"]
     fn deposit_proof_matches_onchain_verifier() {
@@ -624,7 +959,7 @@ mod tests {
             })
             .collect();
 
-        let result = verify_groth16(&verifier, &proof_bytes, &public_inputs);
+        let result = verify_proof(&verifier, &proof_bytes, &public_inputs);
         if result.is_err() {
             dump_pairing_terms("deposit_proof_matches_onchain_verifier");
             dump_scalar_mul_records("deposit_proof_matches_onchain_verifier");
@@ -657,7 +992,7 @@ mod tests {
             })
             .collect();
 
-        let result = verify_groth16(&verifier, &proof_bytes, &public_inputs);
+        let result = verify_proof(&verifier, &proof_bytes, &public_inputs);
         if result.is_err() {
             dump_pairing_terms("live_dumped_deposit_proof_still_fails");
             dump_scalar_mul_records("live_dumped_deposit_proof_still_fails");
@@ -705,8 +1040,7 @@ mod tests {
 
     #[test]
     fn vk_ic_point_scalar_mul_identity() {
-        let verifier = load_packed_key();
-        let key = load_verifier_key(&verifier.verifying_key).expect("unable to decode verifier key");
+        let key = load_verifier_key(&packed_deposit_key()).expect("unable to decode verifier key");
         let point = key.ic[1];
         let mut scalar = [0u8; 32];
         scalar[31] = 1;
@@ -717,8 +1051,7 @@ mod tests {
 
     #[test]
     fn debug_packed_beta() {
-        let verifier = load_packed_key();
-        let key = load_verifier_key(&verifier.verifying_key).expect("unable to decode verifier key");
+        let key = load_verifier_key(&packed_deposit_key()).expect("unable to decode verifier key");
         println!("Packed beta_g2:");
         println!("  x.c1 (0-31):   {}", STANDARD.encode(&key.beta_g2[0..32]));
         println!("  x.c0 (32-63):  {}", STANDARD.encode(&key.beta_g2[32..64]));
@@ -734,11 +1067,17 @@ mod tests {
     }
 
     #[test]
-    fn reduce_mod_order_matches_snarkjs_commitment() {
-        // Test that a value already within the field modulus is unchanged
+    fn snarkjs_commitment_is_canonical() {
         let public_input_be = decode_base64_array::<32>("BbUNpP4upFSSo5lZKpo1ddfTUWJfrvE5YcSVkisu2vM=");
-        let reduced = reduce_mod_order_be(&public_input_be);
-        assert_eq!(reduced, public_input_be, "public input should already be within the field modulus");
+        assert!(require_canonical(&public_input_be).is_ok());
+    }
+
+    #[test]
+    fn public_input_at_or_above_scalar_modulus_is_rejected() {
+        let verifier = load_packed_key();
+        let proof = [0u8; 256];
+        let err = verify_proof(&verifier, &proof, &[SCALAR_MODULUS_BE, SCALAR_MODULUS_BE]).unwrap_err();
+        assert_eq!(err, error!(ShieldError::NonCanonicalFieldElement));
     }
 
     #[test]
@@ -755,8 +1094,7 @@ mod tests {
 
     #[test]
     fn ic_points_roundtrip_through_scalar_mul() {
-        let verifier = load_packed_key();
-        let key = load_verifier_key(&verifier.verifying_key).expect("unable to decode verifier key");
+        let key = load_verifier_key(&packed_deposit_key()).expect("unable to decode verifier key");
         let mut scalar = [0u8; 32];
         scalar[31] = 1;
         for (idx, point) in key.ic.iter().enumerate() {
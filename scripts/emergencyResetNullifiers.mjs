@@ -12,6 +12,7 @@ const PROGRAM_ID = new PublicKey('3KN2qrmEtPyk9WGu9jJSzLerxU8AUXAy8Dp6bqw5APDz')
 
 // Seeds for PDAs (must match the Rust program constants)
 const NULLIFIER_SEED = Buffer.from('nullifiers');
+const ROLE_SEED = Buffer.from('role');
 
 // Instruction discriminator for emergency_reset_nullifiers
 function getInstructionDiscriminator(name) {
@@ -26,13 +27,17 @@ async function main() {
   const walletKeypair = Keypair.fromSecretKey(
     Uint8Array.from(JSON.parse(fs.readFileSync(walletPath, 'utf-8')))
   );
-  console.log('Payer wallet:', walletKeypair.publicKey.toBase58());
+  console.log('Guardian wallet:', walletKeypair.publicKey.toBase58());
 
   // Connect to devnet
   const connection = new Connection('https://api.devnet.solana.com', 'confirmed');
   
   // Derive PDA
   const [nullifierSetPda] = PublicKey.findProgramAddressSync([NULLIFIER_SEED], PROGRAM_ID);
+  const [guardianRolePda] = PublicKey.findProgramAddressSync(
+    [ROLE_SEED, walletKeypair.publicKey.toBuffer()],
+    PROGRAM_ID
+  );
   
   console.log('Nullifier Set PDA:', nullifierSetPda.toBase58());
   
@@ -53,7 +58,8 @@ async function main() {
   const instruction = new TransactionInstruction({
     programId: PROGRAM_ID,
     keys: [
-      { pubkey: walletKeypair.publicKey, isSigner: true, isWritable: true },  // payer
+      { pubkey: walletKeypair.publicKey, isSigner: true, isWritable: true },  // guardian
+      { pubkey: guardianRolePda, isSigner: false, isWritable: false },         // guardian_role
       { pubkey: nullifierSetPda, isSigner: false, isWritable: true },          // nullifier_set
     ],
     data: discriminator,
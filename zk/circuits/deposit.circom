@@ -10,8 +10,7 @@ template Deposit() {
     signal input blinding;
     signal input expectedCommitment;
 
-// deposit.circom placeholder
-// Replace with actual circuit code
+    component poseidon = Poseidon(4);
     poseidon.inputs[0] <== secret;
     poseidon.inputs[1] <== amount;
     poseidon.inputs[2] <== tokenMint;
@@ -22,4 +21,4 @@ template Deposit() {
     expectedCommitment === commitment;
 }
 
-component main { public [expectedCommitment] } = Deposit();
+component main { public [expectedCommitment, amount, tokenMint] } = Deposit();
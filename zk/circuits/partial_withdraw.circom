@@ -33,6 +33,8 @@ template PartialWithdraw() {
     // Public signals
     signal input nullifier;
     signal input receiver;  // recipient's wallet address (for transparent withdrawal)
+    signal input relayer;     // wallet paid relayerFee out of withdrawAmount (0 if none)
+    signal input relayerFee;
 
     // 1. Verify input note commitment exists in tree
     component noteHash = Poseidon(4);
@@ -66,7 +68,13 @@ template PartialWithdraw() {
     changeNoteHash.inputs[2] <== tokenMint;
     changeNoteHash.inputs[3] <== changeBlinding;
 
-    // 5. Output public signals
+    // 5. Bind the relayer and its fee; unconstrained public inputs could be altered in the proof
+    signal relayerSquare;
+    signal relayerFeeSquare;
+    relayerSquare <== relayer * relayer;
+    relayerFeeSquare <== relayerFee * relayerFee;
+
+    // 6. Output public signals
     signal output claimedReceiver;
     signal output claimedWithdrawAmount;
     signal output changeCommitment;
@@ -76,4 +84,4 @@ template PartialWithdraw() {
     changeCommitment <== changeNoteHash.out;
 }
 
-component main { public [merkleRoot, nullifier, receiver, withdrawAmount] } = PartialWithdraw();
+component main { public [tokenMint, merkleRoot, nullifier, receiver, withdrawAmount, relayer, relayerFee] } = PartialWithdraw();
@@ -3,5 +3,62 @@ pragma circom 2.1.9;
 include "../node_modules/circomlib/circuits/poseidon.circom";
 include "./merkle.circom";
 
-// withdraw.circom placeholder
-// Replace with actual circuit code
+// Full Withdrawal: spend one note and withdraw its whole amount to a transparent wallet
+// The program pays relayerFee out of the amount to the relayer and the rest to the receiver
+template Withdraw() {
+    var TREE_HEIGHT = 20;
+
+    // Input note secrets
+    signal input inSecret;
+    signal input inAmount;
+    signal input blinding;
+    signal input rho;
+
+    // Merkle proof for input note
+    signal input pathElements[TREE_HEIGHT];
+    signal input pathIndices[TREE_HEIGHT];
+    signal input merkleRoot;
+
+    // Public signals
+    signal input receiver;    // recipient's wallet address (for transparent withdrawal)
+    signal input nullifier;
+    signal input relayer;     // wallet paid relayerFee out of the amount (0 if none)
+    signal input relayerFee;
+    signal input tokenMint;   // note mint; the program pays from this mint's vault
+
+    // 1. Verify input note commitment exists in tree
+    component noteHash = Poseidon(4);
+    noteHash.inputs[0] <== inSecret;
+    noteHash.inputs[1] <== inAmount;
+    noteHash.inputs[2] <== tokenMint;
+    noteHash.inputs[3] <== blinding;
+
+    component treeCheck = MerkleTreeInclusionProof(TREE_HEIGHT);
+    treeCheck.leaf <== noteHash.out;
+    for (var i = 0; i < TREE_HEIGHT; i++) {
+        treeCheck.pathElements[i] <== pathElements[i];
+        treeCheck.pathIndex[i] <== pathIndices[i];
+    }
+    treeCheck.root === merkleRoot;
+
+    // 2. Verify nullifier is correctly computed
+    component nullifierCheck = Poseidon(2);
+    nullifierCheck.inputs[0] <== inSecret;
+    nullifierCheck.inputs[1] <== rho;
+    nullifier === nullifierCheck.out;
+
+    // 3. Bind the relayer and its fee; unconstrained public inputs could be altered in the proof
+    signal relayerSquare;
+    signal relayerFeeSquare;
+    relayerSquare <== relayer * relayer;
+    relayerFeeSquare <== relayerFee * relayerFee;
+
+    // 4. Output public signals
+    signal output claimedReceiver;
+    signal output claimedAmount;
+
+    claimedReceiver <== receiver;
+    claimedAmount <== inAmount;
+}
+
+component main { public [merkleRoot, receiver, nullifier, relayer, relayerFee, tokenMint] } = Withdraw();
@@ -1,7 +1,7 @@
 import { execSync } from 'child_process';
 import { join } from 'path';
-import { existsSync } from 'fs';
-import { BUILD_DIR, KEYS_DIR, CIRCUITS, ensureDirs } from './utils.js';
+import { existsSync, readFileSync } from 'fs';
+import { BUILD_DIR, KEYS_DIR, CIRCUITS, PUBLIC_INPUTS, ensureDirs } from './utils.js';
 
 const PTAU = process.env.PTAU_PATH || join(KEYS_DIR, 'powersOfTau28_hez_final_15.ptau');
 
@@ -19,4 +19,10 @@ for (const circuit of CIRCUITS) {
   console.log(`[setup] groth16 for ${circuit}`);
   execSync(`npx snarkjs groth16 setup ${r1cs} ${PTAU} ${zkey}`, { stdio: 'inherit' });
   execSync(`npx snarkjs zkey export verificationkey ${zkey} ${vkey}`, { stdio: 'inherit' });
+  const expected = PUBLIC_INPUTS[circuit];
+  const { nPublic } = JSON.parse(readFileSync(vkey, 'utf8'));
+  if (expected !== undefined && nPublic !== expected) {
+    console.error(`[setup] ${circuit} has ${nPublic} public inputs; the program expects ${expected}`);
+    process.exit(1);
+  }
 }
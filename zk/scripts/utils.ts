@@ -7,6 +7,14 @@ export const KEYS_DIR = join(ROOT, 'keys');
 
 export const CIRCUITS = ['deposit', 'transfer', 'transfer-multi', 'withdraw', 'partial_withdraw', 'consolidate'];
 
+// Public signal count of each circuit's on-chain PublicInputLayout; finalize_verifier
+// refuses a key that differs. Circuits whose count depends on the note count are omitted.
+export const PUBLIC_INPUTS: Record<string, number> = {
+  deposit: 4,
+  withdraw: 8,
+  partial_withdraw: 10,
+};
+
 export function ensureDirs() {
   mkdirSync(BUILD_DIR, { recursive: true });
   mkdirSync(KEYS_DIR, { recursive: true });
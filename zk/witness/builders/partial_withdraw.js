@@ -1,4 +1,4 @@
-export function serializePartialWithdrawWitness({ inputNote, merkleProof, withdrawAmount, changeNote, receiver }) {
+export function serializePartialWithdrawWitness({ inputNote, merkleProof, withdrawAmount, changeNote, receiver, relayer = 0n, relayerFee = 0n, }) {
     return {
         inSecret: inputNote.secret.toString(),
         inAmount: inputNote.amount.toString(),
@@ -14,6 +14,8 @@ export function serializePartialWithdrawWitness({ inputNote, merkleProof, withdr
         changeBlinding: changeNote.blinding.toString(),
         nullifier: inputNote.nullifier.toString(),
         receiver: receiver.toString(),
+        relayer: relayer.toString(),
+        relayerFee: relayerFee.toString(),
     };
 }
 export function serializePartialWithdrawPublicInputs(witness) {
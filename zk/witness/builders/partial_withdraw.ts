@@ -7,6 +7,8 @@ export interface PartialWithdrawWitnessInput {
   withdrawAmount: bigint;
   changeNote: Note;  // change note back to sender
   receiver: bigint;  // recipient's wallet address as field element
+  relayer?: bigint;     // relayer wallet as field element; 0 when submitting directly
+  relayerFee?: bigint;  // paid to the relayer out of withdrawAmount
 }
 
 export interface PartialWithdrawWitness {
@@ -29,6 +31,8 @@ export interface PartialWithdrawWitness {
   // Public
   nullifier: string;
   receiver: string;
+  relayer: string;
+  relayerFee: string;
 }
 
 export function serializePartialWithdrawWitness({ 
@@ -36,7 +40,9 @@ export function serializePartialWithdrawWitness({
   merkleProof, 
   withdrawAmount,
   changeNote,
-  receiver 
+  receiver,
+  relayer = 0n,
+  relayerFee = 0n,
 }: PartialWithdrawWitnessInput): PartialWithdrawWitness {
   return {
     inSecret: inputNote.secret.toString(),
@@ -53,6 +59,8 @@ export function serializePartialWithdrawWitness({
     changeBlinding: changeNote.blinding.toString(),
     nullifier: inputNote.nullifier.toString(),
     receiver: receiver.toString(),
+    relayer: relayer.toString(),
+    relayerFee: relayerFee.toString(),
   };
 }
 
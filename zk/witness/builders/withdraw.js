@@ -1,4 +1,4 @@
-export function serializeWithdrawWitness({ inputNote, merkleProof, receiver }) {
+export function serializeWithdrawWitness({ inputNote, merkleProof, receiver, relayer = 0n, relayerFee = 0n, }) {
     return {
         inSecret: inputNote.secret.toString(),
         inAmount: inputNote.amount.toString(),
@@ -10,13 +10,21 @@ export function serializeWithdrawWitness({ inputNote, merkleProof, receiver }) {
         merkleRoot: merkleProof.root.toString(),
         receiver: receiver.toString(),
         nullifier: inputNote.nullifier.toString(),
+        relayer: relayer.toString(),
+        relayerFee: relayerFee.toString(),
     };
 }
+// Public signals in snarkjs order:
+// [claimedReceiver, claimedAmount, merkleRoot, receiver, nullifier, relayer, relayerFee, tokenMint]
 export function serializeWithdrawPublicInputs(witness) {
     return [
+        BigInt(witness.receiver),
+        BigInt(witness.inAmount),
         BigInt(witness.merkleRoot),
         BigInt(witness.receiver),
         BigInt(witness.nullifier),
-        BigInt(witness.inAmount),
+        BigInt(witness.relayer),
+        BigInt(witness.relayerFee),
+        BigInt(witness.tokenMint),
     ];
 }
@@ -5,6 +5,8 @@ export interface WithdrawWitnessInput {
   inputNote: Note;
   merkleProof: MerkleProof;
   receiver: bigint;
+  relayer?: bigint;     // relayer wallet as field element; 0 when submitting directly
+  relayerFee?: bigint;  // paid to the relayer out of the note amount
 }
 
 export interface WithdrawWitness {
@@ -18,9 +20,17 @@ export interface WithdrawWitness {
   merkleRoot: string;
   receiver: string;
   nullifier: string;
+  relayer: string;
+  relayerFee: string;
 }
 
-export function serializeWithdrawWitness({ inputNote, merkleProof, receiver }: WithdrawWitnessInput): WithdrawWitness {
+export function serializeWithdrawWitness({
+  inputNote,
+  merkleProof,
+  receiver,
+  relayer = 0n,
+  relayerFee = 0n,
+}: WithdrawWitnessInput): WithdrawWitness {
   return {
     inSecret: inputNote.secret.toString(),
     inAmount: inputNote.amount.toString(),
@@ -32,14 +42,22 @@ export function serializeWithdrawWitness({ inputNote, merkleProof, receiver }: W
     merkleRoot: merkleProof.root.toString(),
     receiver: receiver.toString(),
     nullifier: inputNote.nullifier.toString(),
+    relayer: relayer.toString(),
+    relayerFee: relayerFee.toString(),
   };
 }
 
-export function serializeWithdrawPublicInputs(witness: WithdrawWitness): [bigint, bigint, bigint, bigint] {
+// Public signals in snarkjs order:
+// [claimedReceiver, claimedAmount, merkleRoot, receiver, nullifier, relayer, relayerFee, tokenMint]
+export function serializeWithdrawPublicInputs(witness: WithdrawWitness): bigint[] {
   return [
+    BigInt(witness.receiver),
+    BigInt(witness.inAmount),
     BigInt(witness.merkleRoot),
     BigInt(witness.receiver),
     BigInt(witness.nullifier),
-    BigInt(witness.inAmount),
+    BigInt(witness.relayer),
+    BigInt(witness.relayerFee),
+    BigInt(witness.tokenMint),
   ];
 }